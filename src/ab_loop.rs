@@ -0,0 +1,59 @@
+use crate::utils::format_time::format_time;
+
+#[derive(Clone, Copy)]
+pub enum Marker {
+    A,
+    B,
+}
+
+// Two points playback keeps going round between once both are set
+#[derive(Default)]
+pub struct AbLoop {
+    a: Option<f32>,
+    b: Option<f32>,
+}
+
+impl AbLoop {
+    // Set a marker at the position, returns what to tell the viewer. Setting B
+    // again while looping turns the loop off
+    pub fn mark(&mut self, marker: Marker, position: f32) -> String {
+        match marker {
+            Marker::A => {
+                self.a = Some(position);
+
+                // A loop can't end before it starts
+                if self.b.is_some_and(|b| b <= position) {
+                    self.b = None;
+                }
+
+                format!("A set at {}", format_time(position as u64))
+            }
+            Marker::B if self.b.is_some() => {
+                *self = Self::default();
+
+                "A-B loop off".to_string()
+            }
+            Marker::B => match self.a {
+                Some(a) if position > a => {
+                    self.b = Some(position);
+
+                    format!(
+                        "Looping {} to {}",
+                        format_time(a as u64),
+                        format_time(position as u64)
+                    )
+                }
+                Some(_) => "B has to come after A".to_string(),
+                None => "Set A first".to_string(),
+            },
+        }
+    }
+
+    // Where to go back to when playback has reached B
+    pub fn restart_at(&self, position: f32) -> Option<f32> {
+        match (self.a, self.b) {
+            (Some(a), Some(b)) if position >= b => Some(a),
+            _ => None,
+        }
+    }
+}
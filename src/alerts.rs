@@ -0,0 +1,90 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+// How long the screen stays inverted for a visual flash
+const FLASH_DURATION: Duration = Duration::from_millis(150);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertEvent {
+    // Playback reached the end or was stopped
+    End,
+    // The stream was lost and playback gave up
+    Error,
+    // A stream that was waited for with --wait-for-stream went live
+    Online,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum AlertKind {
+    Bell,
+    Flash,
+}
+
+// Bell or flash for a playback event, from --alert EVENT=KIND
+#[derive(Clone, Copy, Debug)]
+pub struct Alert {
+    event: AlertEvent,
+    kind: AlertKind,
+}
+
+// Ring every alert set up for the event
+pub fn ring(alerts: &[Alert], event: AlertEvent) {
+    let mut stdout = io::stdout();
+
+    for alert in alerts.iter().filter(|alert| alert.event == event) {
+        match alert.kind {
+            AlertKind::Bell => {
+                let _ = stdout.write_all(b"\x07");
+                let _ = stdout.flush();
+            }
+            // Reverse video for the whole screen, then back
+            AlertKind::Flash => {
+                let _ = stdout.write_all(b"\x1b[?5h");
+                let _ = stdout.flush();
+
+                thread::sleep(FLASH_DURATION);
+
+                let _ = stdout.write_all(b"\x1b[?5l");
+                let _ = stdout.flush();
+            }
+        }
+    }
+}
+
+impl FromStr for Alert {
+    type Err = String;
+
+    // Accepts "<end|error|online>=<bell|flash>"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((event, kind)) = s.split_once('=') else {
+            return Err(format!("invalid alert: {} (expected EVENT=KIND)", s));
+        };
+
+        let event = match event.to_lowercase().as_str() {
+            "end" => AlertEvent::End,
+            "error" => AlertEvent::Error,
+            "online" => AlertEvent::Online,
+            _ => {
+                return Err(format!(
+                    "unknown alert event: {} (expected end, error or online)",
+                    event
+                ))
+            }
+        };
+
+        let kind = match kind.to_lowercase().as_str() {
+            "bell" => AlertKind::Bell,
+            "flash" => AlertKind::Flash,
+            _ => {
+                return Err(format!(
+                    "unknown alert kind: {} (expected bell or flash)",
+                    kind
+                ))
+            }
+        };
+
+        Ok(Alert { event, kind })
+    }
+}
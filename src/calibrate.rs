@@ -0,0 +1,226 @@
+use anyhow::Context;
+use clap::Parser;
+use crossterm::cursor::{self, MoveTo};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Print, ResetColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::capabilities::Capabilities;
+use crate::config::{config_args, save_settings};
+use crate::layout::truncate;
+use crate::paths::config_file;
+use crate::utils::args::{value_name, Args, CalibrateArgs, CharacterMode};
+use crate::video::{Frame, Video};
+
+// Size of the test patterns before they're fitted to the terminal
+const PATTERN_SIZE: (u32, u32) = (1600, 900);
+
+const GAMMA_STEP: f32 = 0.05;
+
+// Cell heights are adjusted in hundredths of the width
+const ASPECT_WIDTH: u32 = 100;
+const ASPECT_STEP: u32 = 5;
+
+// Settings being tuned, starting from the ones in the config
+struct Calibration {
+    mode: String,
+    gamma: f32,
+    // Cell height in hundredths of its width
+    cell_height: u32,
+}
+
+impl Calibration {
+    fn cell_aspect(&self) -> String {
+        format!("{}:{}", ASPECT_WIDTH, self.cell_height)
+    }
+}
+
+// `window calibrate`, test patterns for tuning gamma and cell aspect by eye
+pub fn run_calibrate(args: CalibrateArgs) -> anyhow::Result<()> {
+    let path = config_file(args.config.as_deref())
+        .context("Couldn't find a config directory, pass --config")?;
+    let contents = fs::read_to_string(&path).unwrap_or_default();
+
+    // What playback would use now, the config's flags on their own
+    let current = Args::try_parse_from(
+        [OsString::from("window")]
+            .into_iter()
+            .chain(config_args(&contents))
+            .chain([OsString::from("calibration")]),
+    )
+    .with_context(|| format!("Failed to read config {}", path.display()))?;
+
+    let (cell_width, cell_height) = current
+        .cell_aspect
+        .or(Capabilities::detect()
+            .cell_size
+            .map(|(width, height)| (width as u32, height as u32)))
+        .unwrap_or((1, 2));
+
+    let mut calibration = Calibration {
+        mode: value_name(&args.mode.or(current.mode).unwrap_or(CharacterMode::Auto)),
+        gamma: current.gamma,
+        cell_height: cell_height * ASPECT_WIDTH / cell_width.max(1),
+    };
+
+    let mut stdout = io::stdout();
+
+    execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+    terminal::enable_raw_mode()?;
+
+    let save = adjust(&mut stdout, &mut calibration);
+
+    terminal::disable_raw_mode()?;
+    execute!(stdout, ResetColor, cursor::Show, LeaveAlternateScreen)?;
+
+    if save? {
+        save_settings(
+            &path,
+            &[
+                ("--gamma", format!("{:.2}", calibration.gamma)),
+                ("--cell-aspect", calibration.cell_aspect()),
+            ],
+        )?;
+
+        println!(
+            "Saved gamma {:.2} and cell aspect {} to {}",
+            calibration.gamma,
+            calibration.cell_aspect(),
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+// Redraw with every change until enter saves or escape leaves
+fn adjust(stdout: &mut impl Write, calibration: &mut Calibration) -> anyhow::Result<bool> {
+    loop {
+        draw(stdout, calibration)?;
+
+        match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::Up => calibration.gamma += GAMMA_STEP,
+                KeyCode::Down => {
+                    calibration.gamma = (calibration.gamma - GAMMA_STEP).max(GAMMA_STEP);
+                }
+                KeyCode::Right => calibration.cell_height += ASPECT_STEP,
+                KeyCode::Left => {
+                    calibration.cell_height = calibration
+                        .cell_height
+                        .saturating_sub(ASPECT_STEP)
+                        .max(ASPECT_STEP);
+                }
+                KeyCode::Enter => return Ok(true),
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+fn draw(stdout: &mut impl Write, calibration: &Calibration) -> anyhow::Result<()> {
+    let gamma = format!("{:.2}", calibration.gamma);
+    let cell_aspect = calibration.cell_aspect();
+
+    let mut video = Video::from_args(Args::try_parse_from([
+        "window",
+        "--mode",
+        &calibration.mode,
+        "--gamma",
+        &gamma,
+        "--cell-aspect",
+        &cell_aspect,
+        "--no-audio",
+        "calibration",
+    ])?)?;
+
+    let (width, height) = video.still_size(PATTERN_SIZE);
+    let pattern = test_pattern(width as usize, height as usize);
+
+    queue!(stdout, ResetColor, Clear(ClearType::All))?;
+    video.write_frame(&pattern, 0.0, stdout)?;
+
+    let (columns, rows) = terminal::size()?;
+    let hints = [
+        "Every grey step should stand apart, the circle be round and the square square",
+        &format!(
+            "Gamma {} (up/down)  Cell aspect {} (left/right)  Enter to save, q to quit",
+            gamma, cell_aspect
+        ),
+    ];
+
+    for (row, hint) in [0, rows.saturating_sub(1)].into_iter().zip(hints) {
+        queue!(
+            stdout,
+            ResetColor,
+            MoveTo(0, row),
+            Print(truncate(hint, columns as usize))
+        )?;
+    }
+
+    stdout.flush()?;
+
+    Ok(())
+}
+
+// Color bars over a smooth and a stepped grey ramp on the left, a circle in a
+// square on the right
+fn test_pattern(width: usize, height: usize) -> Frame {
+    const BARS: [(u8, u8, u8); 8] = [
+        (255, 255, 255),
+        (255, 255, 0),
+        (0, 255, 255),
+        (0, 255, 0),
+        (255, 0, 255),
+        (255, 0, 0),
+        (0, 0, 255),
+        (0, 0, 0),
+    ];
+    const STEPS: f32 = 16.0;
+
+    let (pattern_width, pattern_height) = (PATTERN_SIZE.0 as f32, PATTERN_SIZE.1 as f32);
+    let square = pattern_height * 0.6;
+    let center = (pattern_width * 0.8, pattern_height / 2.0);
+
+    let pixel = |x: f32, y: f32| -> (u8, u8, u8) {
+        let (u, v) = (x / pattern_width, y / pattern_height);
+
+        if u >= 0.6 {
+            let (dx, dy) = (x - center.0, y - center.1);
+            let on_circle = ((dx * dx + dy * dy).sqrt() - square / 2.0).abs() < 8.0;
+            let on_square = dx.abs().max(dy.abs()) - square / 2.0;
+
+            return if on_circle || (0.0..16.0).contains(&on_square) {
+                (255, 255, 255)
+            } else {
+                (32, 32, 32)
+            };
+        }
+
+        let grey = |level: f32| {
+            let level = (level * 255.0) as u8;
+            (level, level, level)
+        };
+
+        match v {
+            v if v < 0.4 => BARS[((u / 0.6) * BARS.len() as f32) as usize % BARS.len()],
+            v if v < 0.7 => grey(u / 0.6),
+            _ => grey(((u / 0.6) * STEPS).floor() / (STEPS - 1.0)),
+        }
+    };
+
+    Frame::from_shape_fn((height, width, 3), |(y, x, channel)| {
+        let (r, g, b) = pixel(
+            x as f32 / width.max(1) as f32 * pattern_width,
+            y as f32 / height.max(1) as f32 * pattern_height,
+        );
+
+        [r, g, b][channel]
+    })
+}
@@ -0,0 +1,69 @@
+use crossterm::terminal;
+use std::env;
+
+// What the terminal we're rendering into supports, detected once at startup
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    // DECSET 2026, holds rendering until the whole frame has been written
+    pub synchronized_output: bool,
+    // Pixel size of a single cell (width, height), when the terminal reports it
+    pub cell_size: Option<(u16, u16)>,
+}
+
+impl Capabilities {
+    pub fn detect() -> Self {
+        Self {
+            synchronized_output: detect_synchronized_output(),
+            cell_size: detect_cell_size(),
+        }
+    }
+}
+
+// Many terminals answer the size ioctl with zero pixels, treat that as unknown
+fn detect_cell_size() -> Option<(u16, u16)> {
+    let size = terminal::window_size().ok()?;
+
+    if size.width == 0 || size.height == 0 || size.columns == 0 || size.rows == 0 {
+        return None;
+    }
+
+    Some((size.width / size.columns, size.height / size.rows))
+}
+
+// There's no portable way to query mode 2026 before raw mode is set up, so go by
+// terminals known to support it, anything else simply renders unsynchronized
+fn detect_synchronized_output() -> bool {
+    let term = env::var("TERM").unwrap_or_default();
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+
+    if term == "dumb" || term == "linux" {
+        return false;
+    }
+
+    let known_program = [
+        "WezTerm",
+        "iTerm.app",
+        "ghostty",
+        "vscode",
+        "contour",
+        "rio",
+        "tmux",
+    ]
+    .contains(&term_program.as_str());
+
+    let known_term = [
+        "kitty",
+        "alacritty",
+        "foot",
+        "contour",
+        "wezterm",
+        "ghostty",
+    ]
+    .iter()
+    .any(|name| term.contains(name));
+
+    known_program
+        || known_term
+        || env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var_os("WT_SESSION").is_some()
+}
@@ -0,0 +1,105 @@
+use anyhow::Context;
+use std::io::{BufRead, BufReader};
+use std::process::Child;
+#[cfg(feature = "captions")]
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long a caption stays up when no new line replaces it
+const CAPTION_DURATION: Duration = Duration::from_secs(4);
+
+type Line = Arc<Mutex<Option<(String, Instant)>>>;
+
+// Live captions from an external transcriber, e.g. a whisper.cpp script given
+// {url} and {start} that prints one caption per line as it hears them.
+// Restarted at the new position whenever playback seeks, like external audio
+pub struct Captions {
+    command: String,
+    url: String,
+    child: Option<Child>,
+    // Latest line from the running transcriber. Each start gets a new one so
+    // a line from the previous run can't land after a seek
+    line: Line,
+}
+
+impl Captions {
+    pub fn new(command: String, url: String) -> Self {
+        Self {
+            command,
+            url,
+            child: None,
+            line: Line::default(),
+        }
+    }
+
+    // (Re)start the transcriber at a position in seconds
+    pub fn start(&mut self, position: f32) -> anyhow::Result<()> {
+        self.stop();
+
+        // Placeholders are substituted per argument so the url never goes through a shell
+        let position = format!("{:.3}", position.max(0.0));
+        let mut args = self.command.split_whitespace().map(|arg| {
+            arg.replace("{url}", &self.url)
+                .replace("{start}", &position)
+        });
+
+        let program = args.next().context("Captions command is empty")?;
+
+        let mut child = spawn(&program, args)?;
+
+        let line = Line::default();
+        let reader = BufReader::new(child.stdout.take().unwrap());
+
+        self.line = line.clone();
+        self.child = Some(child);
+
+        thread::spawn(move || {
+            for text in reader.lines().map_while(Result::ok) {
+                let text = text.trim();
+
+                if !text.is_empty() {
+                    *line.lock().unwrap() = Some((text.to_string(), Instant::now()));
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        *self.line.lock().unwrap() = None;
+    }
+
+    // The caption to show right now, if any
+    pub fn current(&self) -> Option<String> {
+        match &*self.line.lock().unwrap() {
+            Some((text, at)) if at.elapsed() < CAPTION_DURATION => Some(text.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(feature = "captions"))]
+fn spawn(_program: &str, _args: impl Iterator<Item = String>) -> anyhow::Result<Child> {
+    anyhow::bail!(
+        "Live captions aren't supported by this build, it was built without the captions feature"
+    )
+}
+
+#[cfg(feature = "captions")]
+fn spawn(program: &str, args: impl Iterator<Item = String>) -> anyhow::Result<Child> {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start captions command {}", program))
+}
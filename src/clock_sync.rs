@@ -0,0 +1,80 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Further behind the shared clock than this is caught up by seeking rather
+// than dropping frames one at a time
+const MAX_DROP_SECS: f64 = 2.0;
+
+// What to do with a frame to stay on the shared clock
+pub enum ClockCorrection {
+    OnTime,
+    // Playback is ahead, hold the frame for this many seconds
+    Wait(f64),
+    // A little behind, skip drawing this frame
+    Drop,
+    // Far behind, jump to this position in seconds
+    Seek(f32),
+}
+
+// Slaves playback to a wall clock epoch, the moment the video started playing
+// for everyone. Instances on NTP synced machines all show the same frame
+pub struct ClockSync {
+    epoch: f64,
+    frame_secs: f64,
+    // Where playback starts at the epoch, in seconds
+    start: f64,
+}
+
+impl ClockSync {
+    pub fn new(epoch: f64, fps: u64, start: f32) -> Self {
+        Self {
+            epoch,
+            frame_secs: 1.0 / fps.max(1) as f64,
+            start: start as f64,
+        }
+    }
+
+    // Seconds played since the epoch, negative while it's still to come
+    fn elapsed(&self) -> f64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+
+        now - self.epoch
+    }
+
+    // Within a frame either way counts as on time. A looping video goes round
+    // every `period` seconds, so everyone is somewhere in the same time round
+    pub fn correct(&self, position: f64, period: Option<f64>) -> ClockCorrection {
+        let elapsed = self.elapsed();
+        let period = period.filter(|&period| period > 0.0);
+
+        // Before the epoch the first frame is held until it comes
+        let target = match period {
+            Some(period) if elapsed > 0.0 => self.start + elapsed % period,
+            _ => self.start + elapsed,
+        };
+
+        let mut drift = position - target;
+
+        // Just before the loop point is barely behind a target that already
+        // wrapped round, not a whole time round ahead of it
+        if let Some(period) = period.filter(|_| elapsed > 0.0) {
+            if drift > period / 2.0 {
+                drift -= period;
+            } else if drift < -period / 2.0 {
+                drift += period;
+            }
+        }
+
+        if drift > self.frame_secs {
+            ClockCorrection::Wait(drift)
+        } else if drift < -MAX_DROP_SECS {
+            ClockCorrection::Seek(target as f32)
+        } else if drift < -self.frame_secs {
+            ClockCorrection::Drop
+        } else {
+            ClockCorrection::OnTime
+        }
+    }
+}
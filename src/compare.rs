@@ -0,0 +1,461 @@
+use anyhow::Context;
+use clap::Parser;
+use crossterm::cursor::{self, MoveTo};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Print, ResetColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use image::imageops::{self, FilterType};
+use image::RgbImage;
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+
+use crate::layout::truncate;
+use crate::stats::Stats;
+use crate::utils::args::{value_name, Args, CompareArgs};
+use crate::utils::format_time::format_time;
+use crate::utils::image_diff::{difference_heatmap, psnr, ssim};
+use crate::utils::sanitize::display_url;
+use crate::video::{frame_image, request_seek, DecodedFrame, SeekRequest, Video};
+
+// Seconds l and k jump, like in playback
+const SEEK_STEP: f32 = 5.0;
+
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+    Split,
+    // Both inputs mixed half and half in one pane, differences show up as
+    // ghosting
+    Blend,
+    // Both inputs with a heatmap of where they differ as a third pane
+    Difference,
+}
+
+impl View {
+    // Switch to `view`, or back to side by side if it's already showing
+    fn toggle(self, view: View) -> View {
+        if self == view {
+            View::Split
+        } else {
+            view
+        }
+    }
+}
+
+// PSNR and SSIM of the latest pair of frames, and their averages since the
+// difference view was opened or playback seeked
+#[derive(Default)]
+struct Similarity {
+    latest: Option<(f64, f64)>,
+    psnr_total: f64,
+    ssim_total: f64,
+    frames: u32,
+}
+
+impl Similarity {
+    fn push(&mut self, psnr: f64, ssim: f64) {
+        self.latest = Some((psnr, ssim));
+        self.psnr_total += psnr;
+        self.ssim_total += ssim;
+        self.frames += 1;
+    }
+
+    fn summary(&self) -> Option<String> {
+        let (psnr, ssim) = self.latest?;
+        let frames = self.frames as f64;
+
+        Some(format!(
+            "PSNR {:.1} dB (avg {:.1})  SSIM {:.3} (avg {:.3})",
+            psnr,
+            self.psnr_total / frames,
+            ssim,
+            self.ssim_total / frames
+        ))
+    }
+}
+
+// One of the inputs being compared. Frames are decoded for the whole picture
+// area and scaled down to whichever pane they're drawn in
+struct Side {
+    name: String,
+    frames: UnboundedReceiver<DecodedFrame>,
+    seek_tx: UnboundedSender<SeekRequest>,
+    stats: Arc<Stats>,
+    fps: u64,
+    // Decoded ahead of the timeline, held until it gets there
+    next: Option<DecodedFrame>,
+    shown: Option<RgbImage>,
+    // Whether the shown frame is new since it was last drawn
+    changed: bool,
+}
+
+impl Side {
+    async fn open(input: &str, flags: &[String], area: (u16, u16)) -> anyhow::Result<Self> {
+        let mut video = video_for(input, flags)?;
+
+        video.viewport = Some((0, 1, area.0, area.1));
+
+        let (frames, seek_tx) = video
+            .fetch_video(video.hw_accel.clone())
+            .await
+            .with_context(|| format!("Failed to open {}", display_url(input)))?;
+
+        Ok(Self {
+            name: display_url(input),
+            frames,
+            seek_tx,
+            stats: video.stats.clone(),
+            fps: video.fps,
+            next: None,
+            shown: None,
+            changed: false,
+        })
+    }
+
+    // Take in every frame up to `position` seconds, showing the latest of them
+    fn advance(&mut self, position: f64) -> anyhow::Result<()> {
+        loop {
+            if self.next.is_none() {
+                self.next = self.frames.try_recv().ok();
+            }
+
+            match self.next.take() {
+                Some((frame, _, pts)) if pts <= position => {
+                    self.shown = Some(frame_image(frame)?);
+                    self.changed = true;
+                }
+                next => {
+                    self.next = next;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn ended(&self) -> bool {
+        self.next.is_none() && self.stats.exhausted() && self.frames.is_empty()
+    }
+
+    async fn seek(&mut self, target: f32) {
+        // The decoder holds on at the end until it's seeked
+        self.stats.set_exhausted(false);
+
+        if let Some((_, frames)) = request_seek(&self.seek_tx, target).await {
+            self.frames = frames;
+            self.next = None;
+        }
+    }
+}
+
+// A renderer for part of the screen, labelled above
+struct Pane {
+    video: Video,
+    column: u16,
+    width: u16,
+}
+
+// `window compare`, two videos side by side on the same timeline
+pub async fn run_compare(args: CompareArgs) -> anyhow::Result<()> {
+    let mut flags = vec!["--fullscreen".to_string(), "--no-audio".to_string()];
+
+    if let Some(mode) = &args.mode {
+        flags.extend(["--mode".to_string(), value_name(mode)]);
+    }
+
+    if let Some(depth) = &args.color_depth {
+        flags.extend(["--color-depth".to_string(), value_name(depth)]);
+    }
+
+    let area = picture_area(terminal::size()?);
+    let mut sides = [
+        Side::open(&args.a, &flags, area).await?,
+        Side::open(&args.b, &flags, area).await?,
+    ];
+
+    let mut stdout = io::stdout();
+
+    execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+    terminal::enable_raw_mode()?;
+
+    let result = play(&mut stdout, &mut sides, &flags).await;
+
+    terminal::disable_raw_mode()?;
+    execute!(stdout, ResetColor, cursor::Show, LeaveAlternateScreen)?;
+
+    result
+}
+
+// Both sides follow one clock, ticking at the faster frame rate of the two
+async fn play(
+    stdout: &mut impl Write,
+    sides: &mut [Side; 2],
+    flags: &[String],
+) -> anyhow::Result<()> {
+    let fps = sides.iter().map(|side| side.fps).max().unwrap_or(30).max(1);
+    let tick = Duration::from_secs_f64(1.0 / fps as f64);
+
+    let mut view = View::Split;
+    let mut swapped = false;
+    let mut paused = false;
+    let mut position = 0.0;
+    let mut last_tick = Instant::now();
+    let mut panes = Vec::new();
+    let mut relayout = true;
+    let mut similarity = Similarity::default();
+
+    loop {
+        while event::poll(Duration::ZERO)? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') => paused = !paused,
+                    KeyCode::Char('x') => {
+                        swapped = !swapped;
+                        relayout = true;
+                    }
+                    KeyCode::Char('b') => {
+                        view = view.toggle(View::Blend);
+                        relayout = true;
+                    }
+                    KeyCode::Char('d') => {
+                        view = view.toggle(View::Difference);
+                        similarity = Similarity::default();
+                        relayout = true;
+                    }
+                    KeyCode::Char(step @ ('l' | 'k')) => {
+                        let step = if step == 'l' { SEEK_STEP } else { -SEEK_STEP };
+
+                        position = (position as f32 + step).max(0.0) as f64;
+
+                        for side in sides.iter_mut() {
+                            side.seek(position as f32).await;
+                        }
+
+                        similarity = Similarity::default();
+
+                        paused = false;
+                    }
+                    _ => {}
+                },
+                Event::Resize(..) => relayout = true,
+                _ => {}
+            }
+        }
+
+        let now = Instant::now();
+
+        if !paused {
+            position += (now - last_tick).as_secs_f64();
+        }
+
+        last_tick = now;
+
+        for side in sides.iter_mut() {
+            side.advance(position)?;
+        }
+
+        // Hold on the last frames once both have run out
+        if sides.iter().all(Side::ended) {
+            paused = true;
+        }
+
+        let size = terminal::size()?;
+
+        if relayout {
+            panes = layout_panes(view, flags, size)?;
+            relayout = false;
+
+            queue!(stdout, ResetColor, Clear(ClearType::All))?;
+
+            for side in sides.iter_mut() {
+                side.changed = true;
+            }
+        }
+
+        let order = if swapped { [1, 0] } else { [0, 1] };
+
+        draw(stdout, sides, &mut panes, view, order, &mut similarity)?;
+
+        let mut status = format_time(position as u64);
+
+        if let Some(summary) = similarity.summary().filter(|_| view == View::Difference) {
+            status = format!("{}  {}", status, summary);
+        }
+
+        queue!(
+            stdout,
+            ResetColor,
+            MoveTo(0, size.1.saturating_sub(1)),
+            Clear(ClearType::CurrentLine),
+            Print(truncate(
+                &format!(
+                    "{}  space to pause, l/k to seek, x to swap, b to blend, d for differences, q to quit",
+                    status
+                ),
+                size.0 as usize
+            ))
+        )?;
+        stdout.flush()?;
+
+        tokio::time::sleep(tick.saturating_sub(now.elapsed())).await;
+    }
+}
+
+// Draw whichever frames are new into the panes, with their labels above
+fn draw(
+    stdout: &mut impl Write,
+    sides: &mut [Side; 2],
+    panes: &mut [Pane],
+    view: View,
+    order: [usize; 2],
+    similarity: &mut Similarity,
+) -> anyhow::Result<()> {
+    let mut labels = Vec::new();
+
+    match view {
+        View::Split | View::Difference => {
+            for (pane, &i) in panes.iter_mut().zip(&order) {
+                let side = &sides[i];
+
+                if let Some(shown) = side.shown.as_ref().filter(|_| side.changed) {
+                    let frame = pane.video.fit_still(shown)?;
+                    pane.video.write_frame(&frame, 0.0, stdout)?;
+                }
+
+                labels.push(side.name.clone());
+            }
+
+            let [a, b] = order.map(|i| &sides[i]);
+
+            if let (Some(pane), Some(shown_a), Some(shown_b)) =
+                (panes.get_mut(2), &a.shown, &b.shown)
+            {
+                if a.changed || b.changed {
+                    let shown_b = same_size(shown_b, shown_a);
+
+                    similarity.push(psnr(shown_a, &shown_b), ssim(shown_a, &shown_b));
+
+                    let frame = pane
+                        .video
+                        .fit_still(&difference_heatmap(shown_a, &shown_b))?;
+                    pane.video.write_frame(&frame, 0.0, stdout)?;
+                }
+            }
+
+            if view == View::Difference {
+                labels.push("Difference".to_string());
+            }
+        }
+        View::Blend => {
+            let [a, b] = order.map(|i| &sides[i]);
+
+            if let (Some(pane), Some(shown_a), Some(shown_b)) =
+                (panes.first_mut(), &a.shown, &b.shown)
+            {
+                if a.changed || b.changed {
+                    let frame = pane.video.fit_still(&blend(shown_a, shown_b))?;
+                    pane.video.write_frame(&frame, 0.0, stdout)?;
+                }
+            }
+
+            labels.push(format!("{} + {}", a.name, b.name));
+        }
+    }
+
+    for side in sides.iter_mut() {
+        side.changed = false;
+    }
+
+    for (pane, label) in panes.iter().zip(labels) {
+        queue!(
+            stdout,
+            ResetColor,
+            MoveTo(pane.column, 0),
+            Print(truncate(&label, pane.width as usize))
+        )?;
+    }
+
+    Ok(())
+}
+
+// A picture scaled to the size of another, to compare them pixel by pixel
+fn same_size<'a>(image: &'a RgbImage, like: &RgbImage) -> Cow<'a, RgbImage> {
+    if image.dimensions() == like.dimensions() {
+        return Cow::Borrowed(image);
+    }
+
+    Cow::Owned(imageops::resize(
+        image,
+        like.width(),
+        like.height(),
+        FilterType::Triangle,
+    ))
+}
+
+// Half of each picture, the second scaled to the first if they differ in size
+fn blend(a: &RgbImage, b: &RgbImage) -> RgbImage {
+    let b = same_size(b, a);
+    let mut blended = a.clone();
+
+    for (pixel, other) in blended.pixels_mut().zip(b.pixels()) {
+        for (channel, other) in pixel.0.iter_mut().zip(other.0) {
+            *channel = ((*channel as u16 + other as u16) / 2) as u8;
+        }
+    }
+
+    blended
+}
+
+// Columns and rows for pictures, leaving a row for labels on top and one for
+// the key hints at the bottom
+fn picture_area((width, height): (u16, u16)) -> (u16, u16) {
+    (width, height.saturating_sub(2))
+}
+
+fn layout_panes(view: View, flags: &[String], size: (u16, u16)) -> anyhow::Result<Vec<Pane>> {
+    let (width, height) = picture_area(size);
+
+    // A column between each of the panes
+    let columns = match view {
+        View::Split => {
+            let half = width.saturating_sub(1) / 2;
+
+            vec![(0, half), (half + 1, half)]
+        }
+        View::Blend => vec![(0, width)],
+        View::Difference => {
+            let third = width.saturating_sub(2) / 3;
+
+            (0..3).map(|i| (i * (third + 1), third)).collect()
+        }
+    };
+
+    columns
+        .into_iter()
+        .map(|(column, width)| {
+            let mut video = video_for("still", flags)?;
+
+            video.viewport = Some((column, 1, width, height));
+
+            Ok(Pane {
+                video,
+                column,
+                width,
+            })
+        })
+        .collect()
+}
+
+fn video_for(input: &str, flags: &[String]) -> anyhow::Result<Video> {
+    let args = ["window"]
+        .into_iter()
+        .map(String::from)
+        .chain(flags.iter().cloned())
+        .chain([input.to_string()]);
+
+    Video::from_args(Args::try_parse_from(args)?)
+}
@@ -0,0 +1,107 @@
+use anyhow::Context;
+use clap::Parser;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, ErrorKind, IsTerminal};
+use std::path::Path;
+
+use crate::paths::config_file;
+use crate::setup::run_setup;
+use crate::utils::args::Args;
+
+// Command line arguments with the config file's in front of them. The config
+// has a flag per line as it'd be typed, e.g. "--mode sextant", and lines
+// starting with # are comments. Flags given on the command line win
+pub fn load_args() -> anyhow::Result<Args> {
+    let args = Args::parse();
+
+    let Some(path) = config_file(args.config.as_deref()) else {
+        return Ok(args);
+    };
+
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        // Not having a config is fine, unless one was asked for. The first
+        // time round someone at a terminal gets to set one up
+        Err(err) if err.kind() == ErrorKind::NotFound && args.config.is_none() => {
+            if !offer_setup(&args) {
+                return Ok(args);
+            }
+
+            run_setup(&path)?
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read config {}", path.display()));
+        }
+    };
+
+    let mut argv = env::args_os();
+    let program = argv.next();
+
+    Ok(Args::parse_from(
+        program
+            .into_iter()
+            .chain(config_args(&contents))
+            .chain(argv),
+    ))
+}
+
+// Arguments in a config, in the order they'd be given on the command line
+pub fn config_args(contents: &str) -> impl Iterator<Item = OsString> + '_ {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .flat_map(|line| match line.split_once(char::is_whitespace) {
+            // The value is the rest of the line, spaces and all
+            Some((flag, value)) => vec![flag.to_string(), value.trim().to_string()],
+            None => vec![line.to_string()],
+        })
+        .map(OsString::from)
+}
+
+// Set flags in the config, replacing the lines they were on before and
+// leaving the rest of it alone
+pub fn save_settings(path: &Path, settings: &[(&str, String)]) -> anyhow::Result<()> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to read config {}", path.display()));
+        }
+    };
+
+    let mut lines: Vec<String> = contents
+        .lines()
+        .filter(|line| {
+            let flag = line.split_whitespace().next().unwrap_or_default();
+
+            !settings.iter().any(|(setting, _)| *setting == flag)
+        })
+        .map(str::to_string)
+        .collect();
+
+    lines.extend(
+        settings
+            .iter()
+            .map(|(flag, value)| format!("{} {}", flag, value)),
+    );
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write config {}", path.display()))
+}
+
+// Only when there's someone to answer, and not when the output goes elsewhere
+fn offer_setup(args: &Args) -> bool {
+    io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && !args.dry_run
+        && !args.kiosk
+        && !args.no_ui
+        && args.serial.is_none()
+}
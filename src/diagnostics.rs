@@ -0,0 +1,139 @@
+use crate::utils::sanitize::{display_url, sanitize};
+
+// Lines of yt-dlp's output shown below the diagnosis
+const LOG_TAIL_LINES: usize = 8;
+
+// Explain why a source failed to open, in plain terms with what to try next,
+// instead of a panic backtrace
+pub fn print_open_failure(url: &str, err: &anyhow::Error) {
+    let yt_dlp_failure = yt_dlp_failure(err);
+
+    let log = match &yt_dlp_failure {
+        Some(YtDlpFailure::Exited { log, .. }) => log,
+        _ => "",
+    };
+
+    eprintln!("Couldn't open {}", display_url(url));
+    eprintln!();
+
+    // The yt-dlp log gets its own section below
+    match &yt_dlp_failure {
+        Some(YtDlpFailure::Exited { code, .. }) => {
+            eprintln!(
+                "  Error: {} (yt-dlp exited with {})",
+                sanitize(&err.to_string()),
+                code
+            )
+        }
+        _ => eprintln!("  Error: {}", sanitize(&format!("{:#}", err))),
+    }
+
+    if let Some((cause, suggestion)) = diagnose(yt_dlp_failure.as_ref(), &format!("{:#}", err)) {
+        eprintln!("  Probable cause: {}", cause);
+        eprintln!("  Try: {}", suggestion);
+    }
+
+    let tail: Vec<&str> = log.lines().filter(|line| !line.trim().is_empty()).collect();
+
+    if !tail.is_empty() {
+        eprintln!();
+        eprintln!("  yt-dlp output:");
+
+        for line in &tail[tail.len().saturating_sub(LOG_TAIL_LINES)..] {
+            eprintln!("    {}", sanitize(line));
+        }
+    }
+}
+
+// Whether opening failed on the video itself rather than on reaching the input,
+// in which case the audio may still play
+pub fn is_video_failure(err: &anyhow::Error) -> bool {
+    let message = format!("{:#}", err).to_lowercase();
+
+    message.contains("no video stream") || message.contains("decoder") || message.contains("codec")
+}
+
+// How running yt-dlp went wrong, when resolving the input got that far
+#[cfg_attr(not(feature = "youtube"), allow(dead_code))]
+enum YtDlpFailure<'a> {
+    NotInstalled,
+    Exited { code: i32, log: &'a str },
+}
+
+#[cfg(feature = "youtube")]
+fn yt_dlp_failure(err: &anyhow::Error) -> Option<YtDlpFailure<'_>> {
+    match err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<youtube_dl::Error>())?
+    {
+        youtube_dl::Error::Io(io_err) if io_err.kind() == std::io::ErrorKind::NotFound => {
+            Some(YtDlpFailure::NotInstalled)
+        }
+        youtube_dl::Error::ExitCode { code, stderr } => Some(YtDlpFailure::Exited {
+            code: *code,
+            log: stderr,
+        }),
+        _ => None,
+    }
+}
+
+// Builds without YouTube support never run yt-dlp
+#[cfg(not(feature = "youtube"))]
+fn yt_dlp_failure(_err: &anyhow::Error) -> Option<YtDlpFailure<'_>> {
+    None
+}
+
+fn diagnose(
+    yt_dlp_failure: Option<&YtDlpFailure>,
+    message: &str,
+) -> Option<(&'static str, &'static str)> {
+    if let Some(YtDlpFailure::NotInstalled) = yt_dlp_failure {
+        return Some((
+            "yt-dlp isn't installed or isn't on PATH",
+            "install yt-dlp, e.g. \"pip install yt-dlp\" or your package manager",
+        ));
+    }
+
+    let message = message.to_lowercase();
+
+    let diagnosis = if message.contains("403") || message.contains("forbidden") {
+        (
+            "the server refused the request (403 from the CDN)",
+            "update yt-dlp with \"yt-dlp -U\", stream URLs also expire so try again",
+        )
+    } else if message.contains("sign in") || message.contains("private video") {
+        (
+            "the video needs an account to watch",
+            "pick a public video, window can't sign in",
+        )
+    } else if message.contains("is not a valid url") || message.contains("unsupported url") {
+        (
+            "yt-dlp doesn't recognise the URL",
+            "check the link, or pass a direct link to the video file",
+        )
+    } else if message.contains("no video stream") {
+        (
+            "the input has no video, only audio or subtitles",
+            "pick an input with a video stream",
+        )
+    } else if message.contains("no decoder for av1") {
+        (
+            "the installed ffmpeg has no AV1 decoder",
+            "install an ffmpeg built with libdav1d, or pick an H.264 version of the video",
+        )
+    } else if message.contains("decoder") || message.contains("codec") {
+        (
+            "the video codec isn't supported by the installed ffmpeg",
+            "re-encode the video, e.g. to H.264, or install an ffmpeg built with the codec",
+        )
+    } else if message.contains("failed to open input") || message.contains("failed to probe") {
+        (
+            "the file doesn't exist or the URL isn't reachable",
+            "check the path or URL and your connection",
+        )
+    } else {
+        return None;
+    };
+
+    Some(diagnosis)
+}
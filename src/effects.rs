@@ -0,0 +1,173 @@
+use std::str::FromStr;
+
+use crate::utils::args::Theme;
+use crate::utils::get_grey::get_grey;
+
+const DEFAULT_POSTERIZE_LEVELS: u8 = 4;
+
+// Frames back the ghost trail compares against by default
+const DEFAULT_TRAIL_FRAMES: u8 = 5;
+
+// Brightness a pixel keeps each frame it stays still in the ghost trail
+const TRAIL_DECAY: f32 = 0.8;
+
+// Luminance difference from the earlier frame that counts as motion
+const TRAIL_THRESHOLD: u8 = 24;
+
+// Effects cycled through at runtime, starting from no effect at all
+pub const EFFECT_CYCLE: [Option<Effect>; 6] = [
+    None,
+    Some(Effect::Invert),
+    Some(Effect::Posterize(DEFAULT_POSTERIZE_LEVELS)),
+    Some(Effect::Sepia),
+    Some(Effect::Grayscale),
+    Some(Effect::Trail(DEFAULT_TRAIL_FRAMES)),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Invert,
+    Posterize(u8),
+    Sepia,
+    Grayscale,
+    // Ghost trail, against the frame this many frames back
+    Trail(u8),
+}
+
+impl Effect {
+    pub fn apply(&self, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        match self {
+            Effect::Invert => (255 - r, 255 - g, 255 - b),
+            Effect::Posterize(levels) => {
+                let step = 255.0 / (levels.saturating_sub(1).max(1)) as f32;
+                let posterize = |v: u8| ((v as f32 / step).round() * step) as u8;
+
+                (posterize(r), posterize(g), posterize(b))
+            }
+            Effect::Sepia => {
+                let (r, g, b) = (r as f32, g as f32, b as f32);
+
+                (
+                    0.393f32
+                        .mul_add(r, 0.769f32.mul_add(g, 0.189 * b))
+                        .min(255.0) as u8,
+                    0.349f32
+                        .mul_add(r, 0.686f32.mul_add(g, 0.168 * b))
+                        .min(255.0) as u8,
+                    0.272f32
+                        .mul_add(r, 0.534f32.mul_add(g, 0.131 * b))
+                        .min(255.0) as u8,
+                )
+            }
+            Effect::Grayscale => {
+                let grey = get_grey(r, g, b);
+
+                (grey, grey, grey)
+            }
+            // Needs earlier frames, so it's applied to the whole frame by GhostTrail
+            Effect::Trail(_) => (r, g, b),
+        }
+    }
+}
+
+// Motion extraction: pixels that changed since an earlier frame are drawn at
+// full brightness while still ones fade out, leaving trails behind whatever moves
+#[derive(Default)]
+pub struct GhostTrail {
+    brightness: Vec<f32>,
+}
+
+impl GhostTrail {
+    // Both frames are packed RGB of the same size
+    pub fn apply(&mut self, pixels: &mut [u8], earlier: &[u8]) {
+        if self.brightness.len() != pixels.len() / 3 {
+            self.brightness = vec![0.0; pixels.len() / 3];
+        }
+
+        for ((pixel, earlier), brightness) in pixels
+            .chunks_exact_mut(3)
+            .zip(earlier.chunks_exact(3))
+            .zip(&mut self.brightness)
+        {
+            let moved = get_grey(pixel[0], pixel[1], pixel[2])
+                .abs_diff(get_grey(earlier[0], earlier[1], earlier[2]))
+                >= TRAIL_THRESHOLD;
+
+            *brightness = if moved {
+                1.0
+            } else {
+                *brightness * TRAIL_DECAY
+            };
+
+            for channel in pixel {
+                *channel = (*channel as f32 * *brightness) as u8;
+            }
+        }
+    }
+}
+
+// Apply a chain of effects in order
+pub fn apply_effects(effects: &[Effect], rgb: (u8, u8, u8)) -> (u8, u8, u8) {
+    effects.iter().fold(rgb, |rgb, effect| effect.apply(rgb))
+}
+
+// Tint the luminance of a pixel with the theme's phosphor color
+pub fn apply_theme(theme: &Theme, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+    let (tint_r, tint_g, tint_b) = match theme {
+        Theme::Phosphor => (51, 255, 51),
+        Theme::Amber => (255, 176, 0),
+        Theme::Matrix => (0, 255, 70),
+    };
+
+    let grey = get_grey(r, g, b) as u16;
+    let scale = |tint: u16| (tint * grey / 255) as u8;
+
+    (scale(tint_r), scale(tint_g), scale(tint_b))
+}
+
+// Character ramp a theme replaces text modes' ramp with, ordered dark to bright
+pub fn theme_ramp(theme: &Theme) -> Option<Vec<u32>> {
+    match theme {
+        Theme::Phosphor | Theme::Amber => None,
+        // Halfwidth katakana, single column wide unlike their fullwidth forms
+        Theme::Matrix => Some(
+            " .:ｰｦｧｨｩｪｫｬｭｮｯｱｲｳｴｵｶｷｸｹｺｻｼｽｾｿﾀﾁﾂﾃﾄﾅﾆﾇﾈﾉﾊﾋﾌﾍﾎﾏﾐﾑﾒﾓﾔﾕﾖﾗﾘﾙﾚﾛﾜﾝ"
+                .chars()
+                .map(|x| x as u32)
+                .collect(),
+        ),
+    }
+}
+
+impl FromStr for Effect {
+    type Err = String;
+
+    // Accepts "invert", "sepia", "grayscale", "posterize", "posterize=<levels>",
+    // "trail" or "trail=<frames>"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (s, None),
+        };
+
+        match (name.to_lowercase().as_str(), value) {
+            ("invert", None) => Ok(Effect::Invert),
+            ("sepia", None) => Ok(Effect::Sepia),
+            ("grayscale" | "greyscale", None) => Ok(Effect::Grayscale),
+            ("posterize", None) => Ok(Effect::Posterize(DEFAULT_POSTERIZE_LEVELS)),
+            ("posterize", Some(levels)) => match levels.parse::<u8>() {
+                Ok(levels) if levels >= 2 => Ok(Effect::Posterize(levels)),
+                _ => Err(format!("invalid posterize levels: {}", levels)),
+            },
+            ("trail", None) => Ok(Effect::Trail(DEFAULT_TRAIL_FRAMES)),
+            ("trail", Some(frames)) => match frames.parse::<u8>() {
+                Ok(frames) if frames >= 1 => Ok(Effect::Trail(frames)),
+                _ => Err(format!("invalid trail frames: {}", frames)),
+            },
+            _ => Err(format!(
+                "unknown effect: {} (expected invert, posterize[=N], sepia, grayscale or trail[=N])",
+                s
+            )),
+        }
+    }
+}
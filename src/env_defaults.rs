@@ -0,0 +1,33 @@
+use std::env;
+
+use crate::utils::args::ColorDepth;
+
+// Defaults picked up from the environment, explicit flags always take precedence
+
+// https://no-color.org, any non-empty value disables color
+pub fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+pub fn color_depth() -> ColorDepth {
+    let colorterm = env::var("COLORTERM").unwrap_or_default();
+    let term = env::var("TERM").unwrap_or_default();
+
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        return ColorDepth::Truecolor;
+    }
+
+    if term.contains("256color") {
+        return ColorDepth::Ansi256;
+    }
+
+    if ["linux", "vt100", "vt220", "ansi", "cygwin"].contains(&term.as_str())
+        || term.ends_with("-16color")
+        || term.ends_with("-color")
+    {
+        return ColorDepth::Ansi16;
+    }
+
+    // Most terminals that don't say otherwise handle truecolor fine
+    ColorDepth::Truecolor
+}
@@ -0,0 +1,262 @@
+use anyhow::Context;
+use serde_json::{json, Value};
+use std::env;
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::Child;
+#[cfg(feature = "audio")]
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Players tried in order when no audio command is given. ffplay comes with
+// ffmpeg but can't open YouTube pages, mpv resolves those through yt-dlp
+#[cfg(feature = "audio")]
+const DEFAULT_PLAYERS: [(&str, &str, bool); 2] = [
+    (
+        "mpv",
+        "mpv --no-video --no-terminal --input-ipc-server={ipc} --start={start} {url}",
+        true,
+    ),
+    (
+        "ffplay",
+        "ffplay -nodisp -autoexit -loglevel quiet -ss {start} {url}",
+        false,
+    ),
+];
+
+// Audio further than this from the frames on screen is moved back in step
+const MAX_DRIFT: f32 = 0.15;
+
+// Longest to wait on the player to answer over IPC, it's asked from the render loop
+#[cfg(unix)]
+const IPC_TIMEOUT: Duration = Duration::from_millis(50);
+
+// Time between snippets while scrubbing, restarting the player any sooner cuts
+// each one off before it's heard
+const SCRUB_INTERVAL: Duration = Duration::from_millis(250);
+
+// Audio played by an external program, e.g. "mpv --no-video --start={start} {url}",
+// restarted at the new position whenever playback seeks. Players that take a
+// JSON IPC socket through {ipc}, like mpv, are also kept in step with the
+// frames as they're shown
+pub struct ExternalAudio {
+    command: String,
+    url: String,
+    // Audio from a different input than the video only loosely follows it, it
+    // keeps playing through seeks and loops instead of restarting
+    loose: bool,
+    child: Option<Child>,
+    last_scrub: Option<Instant>,
+    ipc: Option<PathBuf>,
+    // YouTube pages go through yt-dlp again on every start, far too slow to
+    // restart the player for scrubbing
+    resolves_url: bool,
+}
+
+impl ExternalAudio {
+    pub fn new(command: String, url: String, loose: bool) -> Self {
+        let ipc = (cfg!(unix) && command.contains("{ipc}"))
+            .then(|| env::temp_dir().join(format!("window-audio-{}.sock", std::process::id())));
+        let resolves_url = is_youtube(&url);
+
+        Self {
+            command,
+            url,
+            loose,
+            child: None,
+            last_scrub: None,
+            ipc,
+            resolves_url,
+        }
+    }
+
+    // (Re)start the player at a position in seconds
+    pub fn start(&mut self, position: f32) -> anyhow::Result<()> {
+        if self.loose && self.running() {
+            return Ok(());
+        }
+
+        self.stop();
+
+        // Positions in the video mean nothing to another input
+        let position = if self.loose { 0.0 } else { position };
+
+        // Placeholders are substituted per argument so the url never goes through a shell
+        let position = format!("{:.3}", position.max(0.0));
+        let ipc = self
+            .ipc
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned());
+        // Without a socket to talk over, the option asking for one is left out
+        let mut args = self
+            .command
+            .split_whitespace()
+            .filter(|arg| ipc.is_some() || !arg.contains("{ipc}"))
+            .map(|arg| {
+                arg.replace("{url}", &self.url)
+                    .replace("{start}", &position)
+                    .replace("{ipc}", ipc.as_deref().unwrap_or_default())
+            });
+
+        let program = args.next().context("Audio command is empty")?;
+
+        self.child = Some(spawn(&program, args)?);
+
+        Ok(())
+    }
+
+    // Play a snippet from a seek target that's still being held down, like a
+    // jog wheel. The running player is moved over IPC when it takes one, else
+    // it's restarted unless the url needs resolving first. The seek restarts
+    // the player properly once it's let go
+    pub fn scrub(&mut self, position: f32) -> anyhow::Result<()> {
+        if self.loose
+            || self
+                .last_scrub
+                .is_some_and(|at| at.elapsed() < SCRUB_INTERVAL)
+        {
+            return Ok(());
+        }
+
+        self.last_scrub = Some(Instant::now());
+
+        let sought = self
+            .ipc_request(json!(["seek", position, "absolute+keyframes"]))
+            .is_some();
+
+        if sought || self.resolves_url {
+            return Ok(());
+        }
+
+        self.start(position)
+    }
+
+    // Move the audio back in step when it has drifted from `position`, the
+    // time of the frame on screen, e.g. after dropped frames, a decoder stall
+    // or the player being slow to start
+    pub fn keep_in_sync(&mut self, position: f32) {
+        if self.loose || !self.running() {
+            return;
+        }
+
+        let Some(audio_position) = self
+            .ipc_request(json!(["get_property", "time-pos"]))
+            .and_then(|time_pos| time_pos.as_f64())
+        else {
+            return;
+        };
+
+        if (audio_position as f32 - position).abs() <= MAX_DRIFT {
+            return;
+        }
+
+        // Seeking in place beats restarting, which drifts again by however
+        // long the player takes to start
+        let sought = self
+            .ipc_request(json!(["seek", position, "absolute+exact"]))
+            .is_some();
+
+        if !sought {
+            let _ = self.start(position);
+        }
+    }
+
+    // Send a command over the player's JSON IPC socket, for the data it answers with
+    #[cfg(unix)]
+    fn ipc_request(&self, command: Value) -> Option<Value> {
+        let mut stream = UnixStream::connect(self.ipc.as_ref()?).ok()?;
+
+        stream.set_read_timeout(Some(IPC_TIMEOUT)).ok()?;
+        stream.set_write_timeout(Some(IPC_TIMEOUT)).ok()?;
+        writeln!(stream, "{}", json!({ "command": command })).ok()?;
+
+        // Events can come before the reply, which is the line with an error field
+        for line in BufReader::new(stream).lines() {
+            let reply: Value = serde_json::from_str(&line.ok()?).ok()?;
+
+            if let Some(error) = reply.get("error") {
+                return (error == "success")
+                    .then(|| reply.get("data").cloned().unwrap_or_default());
+            }
+        }
+
+        None
+    }
+
+    #[cfg(not(unix))]
+    fn ipc_request(&self, _command: Value) -> Option<Value> {
+        None
+    }
+
+    pub fn running(&mut self) -> bool {
+        self.child
+            .as_mut()
+            .is_some_and(|child| matches!(child.try_wait(), Ok(None)))
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        // A killed player leaves its socket behind
+        if let Some(path) = &self.ipc {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+fn spawn(_program: &str, _args: impl Iterator<Item = String>) -> anyhow::Result<Child> {
+    anyhow::bail!(
+        "External audio isn't supported by this build, it was built without the audio feature"
+    )
+}
+
+#[cfg(feature = "audio")]
+fn spawn(program: &str, args: impl Iterator<Item = String>) -> anyhow::Result<Child> {
+    Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start audio command {}", program))
+}
+
+// Without the audio feature there's no player to fall back to, so playback is
+// silent unless an audio command is given, which then fails to start
+#[cfg(not(feature = "audio"))]
+pub fn default_audio_cmd(_url: &str) -> Option<String> {
+    None
+}
+
+// Audio command for the first installed player that can play the input
+#[cfg(feature = "audio")]
+pub fn default_audio_cmd(url: &str) -> Option<String> {
+    let youtube = is_youtube(url);
+
+    DEFAULT_PLAYERS
+        .iter()
+        .filter(|(_, _, plays_youtube)| *plays_youtube || !youtube)
+        .find(|(program, _, _)| on_path(program))
+        .map(|(_, command, _)| command.to_string())
+}
+
+fn is_youtube(url: &str) -> bool {
+    url.contains("youtube.com") || url.contains("youtu.be")
+}
+
+#[cfg(feature = "audio")]
+fn on_path(program: &str) -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+
+    env::split_paths(&path)
+        .any(|dir| dir.join(program).is_file() || dir.join(format!("{}.exe", program)).is_file())
+}
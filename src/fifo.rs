@@ -0,0 +1,72 @@
+#[cfg(feature = "ipc")]
+use anyhow::Context;
+use std::str::FromStr;
+#[cfg(feature = "ipc")]
+use tokio::fs::{self, File};
+#[cfg(feature = "ipc")]
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::quality::QualityChange;
+
+// Commands accepted on the input FIFO, one per line
+pub enum FifoCommand {
+    // Relative seek in seconds, negative to go back
+    Seek(f32),
+    Pause,
+    Quit,
+    Quality(QualityChange),
+}
+
+impl FromStr for FifoCommand {
+    type Err = String;
+
+    // Accepts "seek <seconds>", "pause", "quit" or "quality <+|-|level>"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("seek"), Some(seconds), None) => seconds
+                .parse::<f32>()
+                .map(FifoCommand::Seek)
+                .map_err(|_| format!("invalid seek: {}", seconds)),
+            (Some("pause"), None, None) => Ok(FifoCommand::Pause),
+            (Some("quit"), None, None) => Ok(FifoCommand::Quit),
+            (Some("quality"), Some(quality), None) => quality.parse().map(FifoCommand::Quality),
+            _ => Err(format!("unknown command: {}", s.trim())),
+        }
+    }
+}
+
+#[cfg(not(feature = "ipc"))]
+pub async fn read_fifo(_path: String, _tx: UnboundedSender<String>) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "Input FIFOs aren't supported by this build, it was built without the ipc feature"
+    )
+}
+
+// Send every non-empty line written to the FIFO, reopening it each time the
+// writer closes its end. A regular file is read through once
+#[cfg(feature = "ipc")]
+pub async fn read_fifo(path: String, tx: UnboundedSender<String>) -> anyhow::Result<()> {
+    loop {
+        let file = File::open(&path)
+            .await
+            .with_context(|| format!("Failed to open input fifo {}", path))?;
+        let mut lines = BufReader::new(file).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            if tx.send(line).is_err() {
+                return Ok(());
+            }
+        }
+
+        if fs::metadata(&path).await?.is_file() {
+            return Ok(());
+        }
+    }
+}
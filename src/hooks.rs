@@ -0,0 +1,104 @@
+#[cfg(feature = "scripting")]
+use anyhow::Context;
+use serde::Serialize;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "scripting")]
+use crate::utils::args::parse_duration;
+use crate::utils::args::Args;
+
+// Playback state handed to hook commands as JSON on stdin
+#[derive(Serialize)]
+pub struct HookState<'a> {
+    pub event: &'a str,
+    pub position: f32,
+    pub title: &'a str,
+    pub state: &'a str,
+}
+
+// External commands run periodically and on playback events
+#[derive(Default)]
+pub struct Hooks {
+    frame: Option<(Duration, String)>,
+    event: Option<String>,
+}
+
+impl Hooks {
+    #[cfg(not(feature = "scripting"))]
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        if args.on_event_exec.is_some() || args.on_frame_exec_every.is_some() {
+            anyhow::bail!(
+                "Hooks aren't supported by this build, it was built without the scripting feature"
+            );
+        }
+
+        Ok(Self::default())
+    }
+
+    #[cfg(feature = "scripting")]
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        let frame = match args.on_frame_exec_every.as_deref() {
+            Some([interval, command]) => Some((
+                parse_duration(interval)
+                    .map_err(|err| anyhow::anyhow!(err))
+                    .context("Invalid --on-frame-exec-every interval")?,
+                command.clone(),
+            )),
+            _ => None,
+        };
+
+        Ok(Self {
+            frame,
+            event: args.on_event_exec.clone(),
+        })
+    }
+
+    pub fn frame_interval(&self) -> Option<Duration> {
+        self.frame.as_ref().map(|(interval, _)| *interval)
+    }
+
+    pub fn run_frame(&self, state: &HookState) {
+        if let Some((_, command)) = &self.frame {
+            run(command, state);
+        }
+    }
+
+    pub fn run_event(&self, state: &HookState) {
+        if let Some(command) = &self.event {
+            run(command, state);
+        }
+    }
+}
+
+// Fire and forget, a hook never holds up playback and its failures are ignored
+fn run(command: &str, state: &HookState) {
+    let Ok(json) = serde_json::to_string(state) else {
+        return;
+    };
+
+    let mut args = command.split_whitespace();
+
+    let Some(program) = args.next() else {
+        return;
+    };
+
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    if let Ok(mut child) = child {
+        thread::spawn(move || {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = writeln!(stdin, "{}", json);
+            }
+
+            let _ = child.wait();
+        });
+    }
+}
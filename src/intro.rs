@@ -0,0 +1,65 @@
+use crate::utils::get_grey::get_grey;
+use crate::video::Frame;
+
+// Only black stretches this early in the video count as the end of an intro
+const INTRO_WINDOW_SECS: f64 = 300.0;
+
+// Black at the very start is a fade in rather than the end of an intro
+const MIN_INTRO_SECS: f64 = 5.0;
+
+// Shortest black stretch taken as the cut from the intro to the content
+const MIN_BLACK_SECS: f64 = 0.5;
+
+// Brightest a frame can be on average and still count as black
+const BLACK_THRESHOLD: u8 = 16;
+
+// Looks for the first long black stretch in the decoded frames, the usual
+// cut between an intro and the content
+#[derive(Default)]
+pub struct IntroDetector {
+    black_since: Option<f64>,
+    last_time: f64,
+}
+
+impl IntroDetector {
+    // Feed a decoded frame, returns where the content starts once it's found
+    pub fn push(&mut self, time: f64, frame: &Frame) -> Option<f64> {
+        // Seeking backwards breaks up whatever stretch was being tracked
+        if time < self.last_time {
+            self.black_since = None;
+        }
+
+        self.last_time = time;
+
+        if time > INTRO_WINDOW_SECS {
+            return None;
+        }
+
+        if is_black(frame) {
+            self.black_since.get_or_insert(time);
+            return None;
+        }
+
+        match self.black_since.take() {
+            Some(since) if since >= MIN_INTRO_SECS && time - since >= MIN_BLACK_SECS => Some(time),
+            _ => None,
+        }
+    }
+}
+
+// Average brightness of a sparse grid of pixels, the whole frame isn't needed
+fn is_black(frame: &Frame) -> bool {
+    let (height, width) = (frame.shape()[0], frame.shape()[1]);
+    let step = (width.min(height) / 16).max(1);
+
+    let (total, count) = (0..height)
+        .step_by(step)
+        .flat_map(|y| (0..width).step_by(step).map(move |x| (x, y)))
+        .fold((0u64, 0u64), |(total, count), (x, y)| {
+            let grey = get_grey(frame[[y, x, 0]], frame[[y, x, 1]], frame[[y, x, 2]]);
+
+            (total + grey as u64, count + 1)
+        });
+
+    count > 0 && total / count < BLACK_THRESHOLD as u64
+}
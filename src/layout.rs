@@ -0,0 +1,152 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::utils::sanitize::sanitize;
+
+// A piece of a header or footer line. When the line doesn't fit, the elements
+// with the lowest priority are dropped first
+pub struct Element<'a> {
+    priority: u8,
+    content: Content<'a>,
+}
+
+enum Content<'a> {
+    Text(String),
+    // Free text like a title, shrinks down to a minimum width, cut off with an
+    // ellipsis. Right-to-left text inside stays isolated from the rest of the line
+    Truncate(String, usize),
+    // Takes up whatever space is left, rendered at the width it gets
+    Fill(usize, Box<dyn Fn(usize) -> String + 'a>),
+}
+
+impl<'a> Element<'a> {
+    pub fn text(priority: u8, text: String) -> Self {
+        Self {
+            priority,
+            content: Content::Text(sanitize(&text)),
+        }
+    }
+
+    pub fn truncate(priority: u8, text: String, min_width: usize) -> Self {
+        Self {
+            priority,
+            content: Content::Truncate(sanitize(&text), min_width),
+        }
+    }
+
+    pub fn fill(priority: u8, min_width: usize, render: impl Fn(usize) -> String + 'a) -> Self {
+        Self {
+            priority,
+            content: Content::Fill(min_width, Box::new(render)),
+        }
+    }
+
+    fn min_width(&self) -> usize {
+        match &self.content {
+            Content::Text(text) => text.width(),
+            Content::Truncate(text, min_width) => text.width().min(*min_width),
+            Content::Fill(min_width, _) => *min_width,
+        }
+    }
+}
+
+// Lay the elements out left to right with a space around each, in at most
+// `width` columns
+pub fn layout(width: usize, mut elements: Vec<Element>) -> String {
+    let needed = |elements: &[Element]| {
+        elements.iter().map(Element::min_width).sum::<usize>() + elements.len() + 1
+    };
+
+    // The last element left is cut to fit instead of dropped
+    while elements.len() > 1 && needed(&elements) > width {
+        let lowest = (0..elements.len())
+            .min_by_key(|&i| elements[i].priority)
+            .unwrap();
+
+        elements.remove(lowest);
+    }
+
+    let mut spare = width.saturating_sub(needed(&elements));
+
+    // Truncated text grows back first, fills share what's left after that
+    let mut widths: Vec<usize> = elements
+        .iter()
+        .map(|element| {
+            let min_width = element.min_width();
+
+            match &element.content {
+                Content::Truncate(text, _) => {
+                    let grown = (text.width() - min_width).min(spare);
+
+                    spare -= grown;
+
+                    // Cut short on its own rather than losing the closing mark
+                    // when the whole line gets clipped
+                    (min_width + grown).min(width.saturating_sub(2))
+                }
+                _ => min_width,
+            }
+        })
+        .collect();
+
+    let fills: Vec<usize> = (0..elements.len())
+        .filter(|&i| matches!(elements[i].content, Content::Fill(..)))
+        .collect();
+
+    for (n, &i) in fills.iter().enumerate() {
+        // Earlier fills take the remainder of an uneven split
+        widths[i] += spare / fills.len() + usize::from(n < spare % fills.len());
+    }
+
+    let line = elements
+        .iter()
+        .zip(widths)
+        .map(|(element, width)| match &element.content {
+            Content::Text(text) => text.clone(),
+            Content::Truncate(text, _) => isolate(&truncate(text, width)),
+            Content::Fill(_, render) => render(width),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    truncate(&format!(" {} ", line), width)
+}
+
+// Cut text down to `width` columns, ending in an ellipsis when anything was cut
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+
+    for c in text.chars() {
+        let c_width = c.width().unwrap_or(0);
+
+        // Leave a column for the ellipsis
+        if used + c_width + 1 > width {
+            break;
+        }
+
+        truncated.push(c);
+        used += c_width;
+    }
+
+    if width > 0 {
+        truncated.push('…');
+        used += 1;
+    }
+
+    // A wide character that didn't fit leaves a column to pad, so whatever
+    // follows stays where the layout put it
+    truncated.extend(std::iter::repeat_n(' ', width.saturating_sub(used)));
+
+    truncated
+}
+
+// Wrap text in first strong isolate and pop directional isolate marks, so a
+// right-to-left title is laid out on its own without reordering its
+// neighbours in terminals that do bidi. Both marks are zero width
+fn isolate(text: &str) -> String {
+    format!("\u{2068}{}\u{2069}", text)
+}
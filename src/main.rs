@@ -1,251 +1,1235 @@
+use ab_loop::{AbLoop, Marker};
+use alerts::{ring, Alert, AlertEvent};
+use calibrate::run_calibrate;
+use captions::Captions;
 use clap::Parser;
-use crossterm::event::{read, Event, KeyCode, KeyModifiers};
+use clock_sync::{ClockCorrection, ClockSync};
+use compare::run_compare;
+use crossterm::event::{
+    poll, read, DisableFocusChange, EnableFocusChange, Event, KeyCode, KeyEventKind, KeyModifiers,
+};
 use crossterm::{
     cursor::{self, MoveTo},
-    execute,
+    execute, queue,
     style::{self},
-    terminal::{self, Clear, ClearType},
+    terminal::{
+        self, BeginSynchronizedUpdate, Clear, ClearType, EndSynchronizedUpdate,
+        EnterAlternateScreen, LeaveAlternateScreen,
+    },
 };
-use std::io::{self, BufWriter, Write};
-use std::sync::Arc;
+use diagnostics::{is_video_failure, print_open_failure};
+use external_audio::ExternalAudio;
+use fifo::{read_fifo, FifoCommand};
+use hooks::{HookState, Hooks};
+use metrics::serve_metrics;
+use modes::run_modes;
+use now_playing::remove_now_playing;
+use output::{lock_output, OutputThread};
+use pacer::Pacer;
+use quality::QualityChange;
+use recorder::Recorder;
+use replay::{ReplayBuffer, REPLAY_SECS};
+use stats::{CountingWriter, Stats};
+use std::collections::VecDeque;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 use std::{process::exit, time::Duration};
+use timeshift::Timeshift;
 use tokio::sync::mpsc::UnboundedSender;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
 use tokio::{
     sync::mpsc::{unbounded_channel, UnboundedReceiver},
     time::Instant,
 };
+use ttyrec::Ttyrec;
 use utils::{
-    args::{Args, CharacterMode, ScaleMode},
+    args::{CalibrateArgs, CharacterMode, CompareArgs, ModesArgs, OnUnfocus, ScaleMode},
     calculate_fps::calculate_fps,
     ffprobe::DurationType,
+    format_time::format_time,
+    sanitize::{display_url, sanitize},
+    schedule::duration_until,
 };
-use video::{Frame, Video};
+use video::{request_seek, DecodedFrame, SeekRequest, Video};
+use video_rs::Frame;
 
+mod ab_loop;
+mod alerts;
+mod calibrate;
+mod capabilities;
+mod captions;
+mod clock_sync;
+mod compare;
+mod config;
+mod diagnostics;
+mod effects;
+mod env_defaults;
+mod external_audio;
+mod fifo;
+mod hooks;
+mod intro;
+mod layout;
+mod metrics;
+mod modes;
+mod motion;
+mod now_playing;
+mod output;
+mod pacer;
+mod paths;
+mod quality;
+mod recorder;
+mod replay;
+mod scopes;
+mod setup;
+mod smoothing;
+mod source;
+mod stats;
+mod subtitles;
+mod timeshift;
+mod ttyrec;
+mod url_refresh;
 mod video;
 mod utils {
     pub mod args;
+    pub mod backoff;
+    pub mod braille;
     pub mod calculate_fps;
+    pub mod color_blind;
+    pub mod cpu_time;
+    pub mod emoji;
     pub mod ffprobe;
     pub mod format_time;
     pub mod get_grey;
+    pub mod image_diff;
+    pub mod quadrant;
+    pub mod quantize;
     pub mod rgb_distance;
-    pub mod step_size;
+    pub mod sanitize;
+    pub mod scene_cut;
+    pub mod schedule;
+    pub mod sextant;
+    pub mod two_color;
     pub mod youtube;
+    pub mod youtube_cache;
 }
 
+// A gap this long after a seek key counts as letting go of it, around the
+// usual delay before key repeat kicks in
+const SEEK_SETTLE: Duration = Duration::from_millis(600);
+
+// How often the audio player is checked for drifting from the frames
+const AUDIO_SYNC_INTERVAL: Duration = Duration::from_secs(2);
+
+// How long the end screen waits for a replay or seek before playback ends
+const END_SCREEN_TIMEOUT: Duration = Duration::from_secs(15);
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Tools other than playback have their own arguments
+    if env::args().nth(1).as_deref() == Some("modes") {
+        return run_modes(ModesArgs::parse_from(env::args().skip(1))).await;
+    }
+
+    if env::args().nth(1).as_deref() == Some("compare") {
+        return run_compare(CompareArgs::parse_from(env::args().skip(1))).await;
+    }
+
+    if env::args().nth(1).as_deref() == Some("calibrate") {
+        return run_calibrate(CalibrateArgs::parse_from(env::args().skip(1)));
+    }
+
     // Parse command line arguments
-    let args = Args::parse();
+    let args = config::load_args()?;
+
+    let (start_at, play_for, wait_for_stream, dry_run) =
+        (args.at, args.play_for, args.wait_for_stream, args.dry_run);
 
     // Initialize "video" with parameters
-    let mut video = Video::from_args(args);
+    let mut video = Video::from_args(args)?;
 
-    // Fetch video frames and frames per second
-    let (frames_recv, seek_tx) = video.fetch_video(video.hw_accel.clone()).await.unwrap();
-    let (render_tx, render_recv) = unbounded_channel::<(Frame, DurationType)>();
+    // Print what would be played and stop there
+    if dry_run {
+        match video.plan().await {
+            Ok(plan) => println!("{}", serde_json::to_string_pretty(&plan)?),
+            Err(err) => {
+                print_open_failure(&video.url, &err);
+                exit(1);
+            }
+        }
 
-    let frames_recv = Arc::new(RwLock::new(frames_recv));
+        return Ok(());
+    }
+
+    // Hold off until the scheduled time, before fetching so live URLs are fresh
+    if let Some(start_at) = start_at {
+        println!(
+            "Waiting until {} to start playing",
+            start_at.format("%H:%M")
+        );
+        tokio::time::sleep(duration_until(start_at)).await;
+    }
+
+    // Fetch video frames and frames per second
+    let (frames_recv, seek_tx) = match wait_for_stream {
+        Some(interval) => fetch_when_live(&mut video, interval).await,
+        None => match video.fetch_video(video.hw_accel.clone()).await {
+            Ok(fetched) => fetched,
+            // The content is still worth listening to
+            Err(err) if video.external_audio.is_some() && is_video_failure(&err) => {
+                return play_audio_only(&video, &err).await;
+            }
+            Err(err) => {
+                print_open_failure(&video.url, &err);
+                exit(1);
+            }
+        },
+    };
 
     let mut stdout = io::stdout();
 
+    if video.alt_screen {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+
     execute!(stdout, Clear(ClearType::All))?;
     execute!(stdout, cursor::Hide)?;
 
+    // Focus events tell us when the terminal goes to the background
+    if video.on_unfocus != OnUnfocus::Ignore {
+        execute!(stdout, EnableFocusChange)?;
+    }
+
     // Spawn a task to handle signal input
-    tokio::spawn(handle_signal_input());
+    tokio::spawn(handle_signal_input(Teardown::from_video(&video)));
+
+    // Spawn a task to stop playback once the scheduled time is up
+    if let Some(play_for) = play_for {
+        let teardown = Teardown::from_video(&video);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(play_for).await;
+            end(&teardown);
+        });
+    }
+
+    let teardown = Teardown::from_video(&video);
 
     // Spawn a task to render video frames
-    let handle_render = tokio::spawn(handle_render(
-        video,
-        seek_tx,
-        render_recv,
-        frames_recv.clone(),
-    ));
-
-    // Forward frames to the render task
-    loop {
-        let mut frames_recv = frames_recv.write().await;
-        let data = match frames_recv.recv().await {
-            Some(data) => data,
-            None => break,
-        };
+    let handle_render = tokio::spawn(handle_render(video, io::stdout(), seek_tx, frames_recv));
+
+    // The terminal has to be restored before the error can be read
+    if let Err(err) = handle_render.await? {
+        restore(&teardown);
+        eprintln!("{}", sanitize(&format!("{:#}", err)));
+        exit(1);
+    }
+
+    Ok(())
+}
 
-        drop(frames_recv);
+// Play just the audio when the video can't be decoded, showing how long it's
+// been playing until the player exits or playback is stopped
+async fn play_audio_only(video: &Video, err: &anyhow::Error) -> anyhow::Result<()> {
+    let external_audio = video.external_audio.clone().unwrap();
+    let started = Instant::now();
+
+    eprintln!(
+        "Couldn't decode the video of {} ({})",
+        display_url(&video.url),
+        sanitize(&format!("{:#}", err))
+    );
+    eprintln!("Playing the audio only, Ctrl-C to stop");
+
+    tokio::spawn(handle_signal_input(Teardown::from_video(video)));
+
+    external_audio.lock().unwrap().start(0.0)?;
+
+    while external_audio.lock().unwrap().running() {
+        eprint!("\r♪ {} ", format_time(started.elapsed().as_secs()));
 
-        render_tx.send(data).unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
     }
 
-    let _ = handle_render.await?;
+    eprintln!();
 
     Ok(())
 }
 
-fn end() {
+// Poll until the stream can be opened, counting down to each check
+async fn fetch_when_live(
+    video: &mut Video,
+    interval: Duration,
+) -> (
+    UnboundedReceiver<DecodedFrame>,
+    UnboundedSender<SeekRequest>,
+) {
+    let mut waited = false;
+
+    loop {
+        match video.fetch_video(video.hw_accel.clone()).await {
+            Ok(fetched) => {
+                if waited {
+                    eprintln!();
+                    ring(&video.alerts, AlertEvent::Online);
+                }
+
+                return fetched;
+            }
+            Err(err) => {
+                if !waited {
+                    eprintln!(
+                        "Waiting for {} to go live ({})",
+                        display_url(&video.url),
+                        sanitize(&err.to_string())
+                    );
+                    waited = true;
+                }
+
+                for remaining in (1..=interval.as_secs().max(1)).rev() {
+                    eprint!("\rChecking again in {}s ", remaining);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+
+                eprint!("\rChecking now...     ");
+            }
+        }
+    }
+}
+
+// Everything needed to restore the terminal and clean up when exiting
+#[derive(Clone)]
+struct Teardown {
+    stats: Arc<Stats>,
+    alt_screen: bool,
+    external_audio: Option<Arc<Mutex<ExternalAudio>>>,
+    captions: Option<Arc<Mutex<Captions>>>,
+    recorder: Option<Arc<Mutex<Recorder>>>,
+    ttyrec: Option<Arc<Mutex<Ttyrec>>>,
+    hooks: Arc<Hooks>,
+    alerts: Vec<Alert>,
+    now_playing_file: Option<String>,
+    title: String,
+}
+
+impl Teardown {
+    fn from_video(video: &Video) -> Self {
+        Self {
+            stats: video.stats.clone(),
+            alt_screen: video.alt_screen,
+            external_audio: video.external_audio.clone(),
+            captions: video.captions.clone(),
+            recorder: video.recorder.clone(),
+            ttyrec: video.ttyrec.clone(),
+            hooks: video.hooks.clone(),
+            alerts: video.alerts.clone(),
+            now_playing_file: video.now_playing_file.clone(),
+            title: video.title.clone(),
+        }
+    }
+}
+
+fn end(teardown: &Teardown) {
+    restore(teardown);
+    exit(0);
+}
+
+// Stop everything playback started and give the terminal back
+fn restore(teardown: &Teardown) {
+    // Frames still queued for the terminal are dropped with the process
+    let _output = lock_output();
+
+    if let Some(external_audio) = &teardown.external_audio {
+        external_audio.lock().unwrap().stop();
+    }
+
+    if let Some(captions) = &teardown.captions {
+        captions.lock().unwrap().stop();
+    }
+
+    if let Some(recorder) = &teardown.recorder {
+        recorder.lock().unwrap().finish();
+    }
+
+    if let Some(ttyrec) = &teardown.ttyrec {
+        ttyrec.lock().unwrap().finish();
+    }
+
+    teardown.hooks.run_event(&HookState {
+        event: "end",
+        position: teardown.stats.position(),
+        title: &teardown.title,
+        state: "stopped",
+    });
+
+    if let Some(path) = &teardown.now_playing_file {
+        remove_now_playing(path);
+    }
+
+    if teardown.stats.failed() {
+        ring(&teardown.alerts, AlertEvent::Error);
+    } else {
+        ring(&teardown.alerts, AlertEvent::End);
+    }
+
     terminal::disable_raw_mode().unwrap();
     let mut stdout = io::stdout();
-    execute!(
-        stdout,
-        cursor::Show,
-        style::ResetColor,
-        MoveTo(0, 0),
-        Clear(ClearType::All)
-    )
-    .unwrap();
-    exit(0);
+
+    execute!(stdout, DisableFocusChange).unwrap();
+
+    // Leaving the alternate screen brings back whatever was there before we started
+    if teardown.alt_screen {
+        execute!(
+            stdout,
+            cursor::Show,
+            style::ResetColor,
+            LeaveAlternateScreen
+        )
+        .unwrap();
+    } else {
+        execute!(
+            stdout,
+            cursor::Show,
+            style::ResetColor,
+            MoveTo(0, 0),
+            Clear(ClearType::All)
+        )
+        .unwrap();
+    }
+
+    if teardown.stats.report {
+        teardown.stats.print_report();
+    }
 }
 
 // Handle signal to quit the application
-async fn handle_signal_input() {
+async fn handle_signal_input(teardown: Teardown) {
     tokio::signal::ctrl_c().await.unwrap();
-    end();
+    end(&teardown);
+}
+
+// Actions requested by the input tasks, applied by the render loop between frames
+enum Action {
+    CycleEffect,
+    CycleScope,
+    TogglePause,
+    FocusChanged(bool),
+    Seek(f32),
+    Quality(QualityChange),
+    Replay,
+    JumpToLive,
+    Next,
+    Mark(Marker),
+    // Target of a seek still being held down
+    SeekPreview(f32),
+}
+
+// Asks the render loop for seeks from outside it, the render loop does the
+// seeking itself so it never shows a frame from before one
+#[derive(Clone)]
+struct Seeker {
+    fps: u64,
+    frames_seen: Arc<RwLock<u64>>,
+    action_tx: UnboundedSender<Action>,
+}
+
+impl Seeker {
+    // Current position in seconds
+    async fn position(&self) -> f32 {
+        *self.frames_seen.read().await as f32 / self.fps as f32
+    }
+
+    // Seek relative to the current position
+    async fn seek_by(&self, seconds: f32) {
+        self.seek_to(self.position().await + seconds);
+    }
+
+    // Seek to a position in seconds
+    fn seek_to(&self, position: f32) {
+        let _ = self.action_tx.send(Action::Seek(position.max(0.0)));
+    }
+}
+
+// Have the decoder seek to `target` seconds and wait for it to hand over the
+// frames from there. Whatever was decoded before stays behind in the old
+// channel, so it can't show up after the seek
+async fn seek(
+    video: &mut Video,
+    seek_tx: &UnboundedSender<SeekRequest>,
+    frames: &mut UnboundedReceiver<DecodedFrame>,
+    frames_seen: &RwLock<u64>,
+    target: f32,
+    playing: bool,
+) {
+    // The decoder holds on at the end until it's seeked, playback shouldn't
+    // end in the meantime
+    video.stats.set_exhausted(false);
+
+    // Without a decoder the stream just runs out like it would have
+    let Some((pts, seeked_frames)) = request_seek(seek_tx, target).await else {
+        return;
+    };
+
+    *frames = seeked_frames;
+    *frames_seen.write().await = (pts * video.fps as f64) as u64;
+
+    // Paused playback starts these again once it's resumed
+    if playing {
+        video.start_audio(pts as f32);
+        video.start_captions(pts as f32);
+    } else if let Some(external_audio) = &video.external_audio {
+        // Scrubbing while paused leaves a snippet playing
+        external_audio.lock().unwrap().stop();
+    }
+
+    video.show_seek_bar();
+
+    video.hooks.run_event(&HookState {
+        event: "seek",
+        position: pts as f32,
+        title: &video.title,
+        state: if playing { "playing" } else { "paused" },
+    });
+}
+
+// Execute commands written to the input FIFO
+async fn handle_fifo_input(
+    path: String,
+    live: bool,
+    seeker: Seeker,
+    action_tx: UnboundedSender<Action>,
+    teardown: Teardown,
+) {
+    let (line_tx, mut line_recv) = unbounded_channel();
+
+    let reader = tokio::spawn(read_fifo(path, line_tx));
+
+    while let Some(line) = line_recv.recv().await {
+        match line.parse::<FifoCommand>() {
+            Ok(FifoCommand::Seek(_)) if live => {
+                teardown.stats.warn("Can't seek a live stream".to_string());
+            }
+            Ok(FifoCommand::Seek(seconds)) => seeker.seek_by(seconds).await,
+            Ok(FifoCommand::Pause) => {
+                let _ = action_tx.send(Action::TogglePause);
+            }
+            Ok(FifoCommand::Quit) => end(&teardown),
+            Ok(FifoCommand::Quality(change)) => {
+                let _ = action_tx.send(Action::Quality(change));
+            }
+            Err(err) => teardown.stats.warn(err),
+        }
+    }
+
+    if let Ok(Err(err)) = reader.await {
+        teardown.stats.warn(err.to_string());
+    }
 }
 
-// Drain the receiver channels
-// A bit buggy though
-async fn drain_receiver(recv: &mut UnboundedReceiver<(Frame, DurationType)>) {
-    while recv.try_recv().is_ok() {}
+// Start replaying the buffered frames. Audio of a live stream keeps going since
+// playback jumps back to the live edge afterwards, otherwise it waits for the
+// video to catch up
+fn start_replay(video: &mut Video, replay_buffer: &mut ReplayBuffer) -> VecDeque<DecodedFrame> {
+    if !video.live {
+        if let Some(external_audio) = &video.external_audio {
+            external_audio.lock().unwrap().stop();
+        }
+
+        if let Some(captions) = &video.captions {
+            captions.lock().unwrap().stop();
+        }
+    }
+
+    video.show_osd(format!("Replaying the last {}s", REPLAY_SECS));
+
+    replay_buffer.take()
 }
 
-// Render video frames to the terminal
-async fn handle_render(
+// Next frame of a live stream, after moving whatever else has arrived into the
+// timeshift so it can't pile up unbounded in the channel
+async fn next_timeshifted(
+    frames: &mut UnboundedReceiver<DecodedFrame>,
+    timeshift: &mut Timeshift,
+) -> Option<DecodedFrame> {
+    while let Ok(data) = frames.try_recv() {
+        timeshift.push(data);
+    }
+
+    match timeshift.pop() {
+        Some(data) => Some(data),
+        None => frames.recv().await,
+    }
+}
+
+// Skip everything buffered for a live stream and carry on from the newest frame
+fn jump_to_live(
+    video: &mut Video,
+    timeshift: &mut Option<Timeshift>,
+    frames: &mut UnboundedReceiver<DecodedFrame>,
+) {
+    if let Some(timeshift) = timeshift {
+        timeshift.clear();
+    }
+
+    while frames.try_recv().is_ok() {}
+    video.behind_live = 0.0;
+    video.show_osd("Back to live".to_string());
+}
+
+// Clear and draw the last frame again laid out for the new terminal size
+fn relayout(
+    video: &mut Video,
+    stdout: &mut impl Write,
+    shown: Option<&(Frame, f64)>,
+) -> anyhow::Result<()> {
+    execute!(stdout, Clear(ClearType::All))?;
+
+    // The offsets move with the terminal size, so everything needs redrawing
+    video.last_frame = None;
+
+    video.write_header(stdout)?;
+
+    if let Some((frame, pts)) = shown {
+        video.write_frame(frame, *pts, stdout)?;
+    }
+
+    stdout.flush()?;
+
+    Ok(())
+}
+
+// Summary shown once a video has played to the end, for a moment or until it's
+// replayed or seeked back into. There's only ever one video, so next quits
+// like q does. Returns where to seek to
+async fn end_screen(
+    video: &mut Video,
+    stdout: &mut impl Write,
+    action_recv: &mut UnboundedReceiver<Action>,
+    resized: &Notify,
+    shown: Option<&(Frame, f64)>,
+    started: Instant,
+    teardown: &Teardown,
+) -> anyhow::Result<f32> {
+    if let Some(external_audio) = &video.external_audio {
+        external_audio.lock().unwrap().stop();
+    }
+
+    if let Some(captions) = &video.captions {
+        captions.lock().unwrap().stop();
+    }
+
+    let watched = started.elapsed();
+    let frames = video.stats.frames.load(Ordering::Relaxed);
+    let dropped = video.stats.dropped_frames.load(Ordering::Relaxed);
+    let deadline = Instant::now() + END_SCREEN_TIMEOUT;
+
+    loop {
+        video.write_end_screen(stdout, watched, frames, dropped)?;
+        stdout.flush()?;
+
+        let action = tokio::select! {
+            action = action_recv.recv() => action,
+            // Ends playback like running out of actions does
+            _ = tokio::time::sleep_until(deadline) => None,
+            _ = resized.notified() => {
+                relayout(video, stdout, shown)?;
+                continue;
+            }
+        };
+
+        match action {
+            Some(Action::Seek(target)) => {
+                // Bring back the part of the video the summary covered
+                video.last_frame = None;
+
+                return Ok(target);
+            }
+            Some(Action::Next) | None => end(teardown),
+            Some(_) => {}
+        }
+    }
+}
+
+// Render video frames to the output, the terminal unless embedded elsewhere
+async fn handle_render<W: Write + Send + 'static>(
     mut video: Video,
-    seek_tx: UnboundedSender<i64>,
-    render_recv: UnboundedReceiver<(Frame, DurationType)>,
-    frames_recv: Arc<RwLock<UnboundedReceiver<(Frame, DurationType)>>>,
+    output: W,
+    seek_tx: UnboundedSender<SeekRequest>,
+    mut frames: UnboundedReceiver<DecodedFrame>,
 ) -> anyhow::Result<()> {
     let started = Instant::now();
     let std_frame_time = Duration::from_micros(1_000_000 / video.fps);
-    let frames_seen = Arc::new(RwLock::new(0));
+    let frames_seen = Arc::new(RwLock::new(0u64));
     let mut frame_times: Vec<Instant> = vec![];
-    let render_recv = Arc::new(RwLock::new(render_recv));
+    let stats = video.stats.clone();
+    let mut paused = false;
+    let mut pacer = Pacer::new(std_frame_time);
+    let mut focused = true;
+    let mut first_frame = true;
+    let mut last_frame_hook = Instant::now();
+    let mut last_now_playing: Option<Instant> = None;
+    let mut last_audio_sync = Instant::now();
+    let mut replay_buffer = ReplayBuffer::new(video.fps);
+    let mut replaying: VecDeque<DecodedFrame> = VecDeque::new();
+    let mut at_end = false;
+    let mut ab_loop = AbLoop::default();
 
-    let mut stdout = BufWriter::new(io::stdout());
+    let mut stdout = OutputThread::spawn(
+        CountingWriter::new(output, stats.clone()),
+        video.ttyrec.clone(),
+    );
 
-    let (mut last_width, mut last_height) = terminal::size()?;
+    // The last frame drawn, to draw again when the layout changes
+    let mut shown: Option<(Frame, f64)> = None;
+    let resized = Arc::new(Notify::new());
+    let resize_notify = resized.clone();
 
     terminal::enable_raw_mode()?;
 
-    let frames_seen_copy = frames_seen.clone();
-    let render_revc_copy = render_recv.clone();
+    let teardown = Teardown::from_video(&video);
+    let teardown_copy = teardown.clone();
+    let (action_tx, mut action_recv) = unbounded_channel::<Action>();
+    let live = video.live;
+
+    // Live streams can't be seeked, so frames arriving while paused are held
+    // on to and played from where playback left off
+    let mut timeshift = live.then(|| Timeshift::new(video.fps, video.timeshift_max.as_secs()));
+    let kiosk = video.kiosk;
+    let skip_intro = video.skip_intro;
+    let clip_start = video.clip_start;
+    let intro_stats = stats.clone();
+
+    // Seeking a live stream isn't possible, so there's nothing to sync it to
+    let clock_sync = video
+        .sync_epoch
+        .filter(|_| !live)
+        .map(|epoch| ClockSync::new(epoch, video.fps, video.clip_start));
+
+    let seeker = Seeker {
+        fps: video.fps,
+        frames_seen: frames_seen.clone(),
+        action_tx: action_tx.clone(),
+    };
+
+    if let Some(path) = video.input_fifo.clone() {
+        tokio::spawn(handle_fifo_input(
+            path,
+            live,
+            seeker.clone(),
+            action_tx.clone(),
+            teardown.clone(),
+        ));
+    }
+
+    if let Some(addr) = video.metrics.clone() {
+        let metrics_stats = stats.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = serve_metrics(&addr, metrics_stats.clone()).await {
+                metrics_stats.warn(format!("{:#}", err));
+            }
+        });
+    }
 
     tokio::spawn(async move {
+        // Seeks on a held down l or k add up and go out as one once it's let go
+        let mut pending_seek = 0.0;
+
         loop {
+            // Key repeat keeps events coming faster than this, a gap means the
+            // key was let go
+            if pending_seek != 0.0 && !poll(SEEK_SETTLE).unwrap_or(false) {
+                seeker.seek_by(pending_seek).await;
+                pending_seek = 0.0;
+
+                continue;
+            }
+
             let ev = read();
+
+            if let Ok(Event::Resize(..)) = ev {
+                resize_notify.notify_one();
+            }
+
+            if let Ok(Event::FocusGained | Event::FocusLost) = ev {
+                let focused = matches!(ev, Ok(Event::FocusGained));
+
+                action_tx.send(Action::FocusChanged(focused)).unwrap();
+            }
+
             if let Ok(Event::Key(event)) = ev {
-                if event.code == KeyCode::Char('q')
-                    || (event.code == KeyCode::Char('c')
-                        && event.modifiers == KeyModifiers::CONTROL)
+                if !kiosk
+                    && (event.code == KeyCode::Char('q')
+                        || (event.code == KeyCode::Char('c')
+                            && event.modifiers == KeyModifiers::CONTROL))
                 {
-                    end();
+                    end(&teardown_copy);
                 }
 
-                if !video.live {
-                    if event.code == KeyCode::Char('l') {
-                        let mut frames_seen = frames_seen_copy.write().await;
-                        let current_time = *frames_seen as f32 / video.fps as f32;
+                if event.code == KeyCode::Char('e') {
+                    action_tx.send(Action::CycleEffect).unwrap();
+                }
 
-                        seek_tx
-                            .send((current_time * 1000.0 + 5000.0) as i64)
-                            .unwrap();
+                if event.code == KeyCode::Char('h') {
+                    action_tx.send(Action::CycleScope).unwrap();
+                }
 
-                        let mut render_recv = render_revc_copy.write().await;
-                        let mut frames_recv = frames_recv.write().await;
+                if matches!(event.code, KeyCode::Char('+') | KeyCode::Char('=')) {
+                    action_tx.send(Action::Quality(QualityChange::Up)).unwrap();
+                }
 
-                        let new_frames = ((current_time + 5.0) * (video.fps as f32)) as u64;
+                if event.code == KeyCode::Char('-') {
+                    action_tx
+                        .send(Action::Quality(QualityChange::Down))
+                        .unwrap();
+                }
 
-                        *frames_seen = new_frames;
+                if event.code == KeyCode::Char(' ') {
+                    action_tx.send(Action::TogglePause).unwrap();
+                }
 
-                        drain_receiver(&mut render_recv).await;
-                        drain_receiver(&mut frames_recv).await;
+                if event.code == KeyCode::Char('i') {
+                    action_tx.send(Action::Replay).unwrap();
+                }
 
-                        drop(render_recv);
-                        drop(frames_recv);
-                        drop(frames_seen);
-                    }
+                if event.code == KeyCode::Char('n') {
+                    action_tx.send(Action::Next).unwrap();
+                }
 
-                    if event.code == KeyCode::Char('k') {
-                        let mut frames_seen = frames_seen_copy.write().await;
-                        let current_time = *frames_seen as f32 / video.fps as f32;
+                if !live {
+                    let step = match event.code {
+                        KeyCode::Char('l') => 5.0,
+                        KeyCode::Char('k') => -5.0,
+                        _ => 0.0,
+                    };
 
-                        seek_tx
-                            .send((current_time * 1000.0 - 5000.0) as i64)
-                            .unwrap();
+                    // Terminals that report releases tell us right away, and
+                    // anything else pressed meanwhile ends the seek too
+                    if pending_seek != 0.0 && (step == 0.0 || event.kind == KeyEventKind::Release) {
+                        seeker.seek_by(pending_seek).await;
+                        pending_seek = 0.0;
+                    }
 
-                        let mut frames_recv = frames_recv.write().await;
-                        let mut render_recv = render_revc_copy.write().await;
+                    if step != 0.0 && event.kind != KeyEventKind::Release {
+                        pending_seek += step;
 
-                        let new_frames = ((current_time - 5.0) * (video.fps as f32)) as u64;
+                        let target = (seeker.position().await + pending_seek).max(0.0);
+                        action_tx.send(Action::SeekPreview(target)).unwrap();
+                    }
+
+                    if event.code == KeyCode::Char('r') {
+                        seeker.seek_to(clip_start);
+                    }
 
-                        *frames_seen = new_frames;
+                    if event.code == KeyCode::Char('a') {
+                        action_tx.send(Action::Mark(Marker::A)).unwrap();
+                    }
 
-                        drain_receiver(&mut render_recv).await;
-                        drain_receiver(&mut frames_recv).await;
+                    if event.code == KeyCode::Char('b') {
+                        action_tx.send(Action::Mark(Marker::B)).unwrap();
+                    }
 
-                        drop(render_recv);
-                        drop(frames_recv);
-                        drop(frames_seen);
+                    if event.code == KeyCode::Char('s') && skip_intro {
+                        if let Some(intro_end) = intro_stats.intro_end() {
+                            seeker.seek_to(intro_end);
+                        }
                     }
+                } else if event.code == KeyCode::Char('l') {
+                    action_tx.send(Action::JumpToLive).unwrap();
                 }
             }
         }
     });
 
-    // while let Some((frame, duration)) = render_recv.recv().await {
     loop {
-        let mut render_recv = render_recv.write().await;
+        if at_end {
+            at_end = false;
+
+            let target = if video.loop_again() {
+                video.clip_start
+            } else {
+                // Nobody's there to replay from the end screen when the keys
+                // don't come from a terminal
+                if video.no_ui || !io::stdin().is_terminal() {
+                    end(&teardown);
+                }
+
+                end_screen(
+                    &mut video,
+                    &mut stdout,
+                    &mut action_recv,
+                    &resized,
+                    shown.as_ref(),
+                    started,
+                    &teardown,
+                )
+                .await?
+            };
 
-        let (frame, duration) = match render_recv.recv().await {
+            seek(
+                &mut video,
+                &seek_tx,
+                &mut frames,
+                &frames_seen,
+                target,
+                true,
+            )
+            .await;
+            replay_buffer.clear();
+            pacer.reset();
+        }
+
+        // Replayed frames come from the buffer, the stream waits meanwhile
+        let in_replay = !replaying.is_empty();
+
+        let (frame, duration, pts) = match replaying.pop_front() {
             Some(data) => data,
-            None => break,
+            None => {
+                let next = async {
+                    match &mut timeshift {
+                        Some(timeshift) => next_timeshifted(&mut frames, timeshift).await,
+                        None => frames.recv().await,
+                    }
+                };
+
+                // A slow stream shouldn't hold up redrawing for a resize
+                let data = tokio::select! {
+                    biased;
+
+                    data = next => data,
+                    _ = resized.notified() => {
+                        relayout(&mut video, &mut stdout, shown.as_ref())?;
+                        continue;
+                    }
+                    // Nothing left to play, unless a seek got in first
+                    _ = stats.wait_exhausted(), if !live => {
+                        at_end = stats.exhausted() && frames.is_empty();
+                        continue;
+                    }
+                };
+
+                let Some(data) = data else {
+                    break;
+                };
+
+                replay_buffer.push(&data);
+
+                data
+            }
         };
 
-        drop(render_recv);
+        video.behind_live = timeshift.as_ref().map_or(0.0, Timeshift::behind);
+
+        // Start external audio with the first frame so startup time doesn't offset it
+        if first_frame {
+            first_frame = false;
+
+            // The decoder has already skipped ahead to --start
+            *frames_seen.write().await = (video.clip_start * video.fps as f32) as u64;
+
+            video.start_audio(video.clip_start);
+
+            video.start_captions(video.clip_start);
+
+            video.hooks.run_event(&video.hook_state("start", "playing"));
+        }
+
+        // A frame taken before a seek isn't worth showing after it
+        let mut sought = false;
 
-        let (width, height) = terminal::size()?;
+        while let Ok(action) = action_recv.try_recv() {
+            match action {
+                Action::CycleEffect => video.cycle_effect(),
+                Action::CycleScope => video.cycle_scope(),
+                Action::TogglePause => paused = !paused,
+                Action::FocusChanged(is_focused) => focused = is_focused,
+                Action::Seek(target) => {
+                    seek(
+                        &mut video,
+                        &seek_tx,
+                        &mut frames,
+                        &frames_seen,
+                        target,
+                        !paused,
+                    )
+                    .await;
+                    replay_buffer.clear();
+                    replaying.clear();
+                    pacer.reset();
+                    sought = true;
+                }
+                Action::Quality(change) => video.change_quality(change),
+                Action::Replay if !in_replay => {
+                    replaying = start_replay(&mut video, &mut replay_buffer);
+                }
+                Action::Replay => {}
+                Action::JumpToLive => jump_to_live(&mut video, &mut timeshift, &mut frames),
+                Action::Next => {}
+                Action::Mark(marker) => {
+                    let position = *frames_seen.read().await as f32 / video.fps as f32;
+                    video.show_osd(ab_loop.mark(marker, position));
+                }
+                Action::SeekPreview(target) => video.preview_seek(target),
+            }
+        }
 
-        if width != last_width || height != last_height {
-            execute!(stdout, Clear(ClearType::All))?;
-            last_width = width;
-            last_height = height;
+        if sought {
+            continue;
         }
 
-        let mut frames_seen_write_lock = frames_seen.write().await;
+        // Hold on to the current frame until playback is resumed
+        if paused {
+            if let Some(external_audio) = &video.external_audio {
+                external_audio.lock().unwrap().stop();
+            }
+
+            if let Some(captions) = &video.captions {
+                captions.lock().unwrap().stop();
+            }
 
-        *frames_seen_write_lock += 1;
+            video.hooks.run_event(&video.hook_state("pause", "paused"));
+            video.write_now_playing("paused", duration);
 
-        drop(frames_seen_write_lock);
+            while paused {
+                let action = tokio::select! {
+                    action = action_recv.recv() => action,
+                    // Keep taking in a live stream, or it piles up in the channel
+                    Some(data) = frames.recv(), if timeshift.is_some() => {
+                        if let Some(timeshift) = &mut timeshift {
+                            timeshift.push(data);
+                        }
+
+                        continue;
+                    }
+                    _ = resized.notified() => {
+                        relayout(&mut video, &mut stdout, shown.as_ref())?;
+                        continue;
+                    }
+                };
+
+                match action {
+                    Some(Action::CycleEffect) => video.cycle_effect(),
+                    Some(Action::CycleScope) => video.cycle_scope(),
+                    Some(Action::FocusChanged(is_focused)) => focused = is_focused,
+                    Some(Action::Seek(target)) => {
+                        seek(
+                            &mut video,
+                            &seek_tx,
+                            &mut frames,
+                            &frames_seen,
+                            target,
+                            false,
+                        )
+                        .await;
+                        replay_buffer.clear();
+                        replaying.clear();
+                    }
+                    Some(Action::Quality(change)) => video.change_quality(change),
+                    Some(Action::Replay) if !in_replay => {
+                        replaying = start_replay(&mut video, &mut replay_buffer);
+                        paused = false;
+                    }
+                    Some(Action::Replay) => {}
+                    Some(Action::JumpToLive) => {
+                        jump_to_live(&mut video, &mut timeshift, &mut frames)
+                    }
+                    Some(Action::Next) => {}
+                    Some(Action::Mark(marker)) => {
+                        let position = *frames_seen.read().await as f32 / video.fps as f32;
+                        video.show_osd(ab_loop.mark(marker, position));
+                    }
+                    Some(Action::SeekPreview(target)) => video.preview_seek(target),
+                    Some(Action::TogglePause) | None => paused = false,
+                }
+            }
+
+            let current_time = *frames_seen.read().await as f32 / video.fps as f32;
+
+            // Resumed into a replay, the audio waits for it to finish
+            if replaying.is_empty() || live {
+                video.start_audio(current_time);
+
+                video.start_captions(current_time);
+            }
+
+            video
+                .hooks
+                .run_event(&video.hook_state("resume", "playing"));
+
+            pacer.reset();
+        }
+
+        // A replay has caught up, go back to where it was started from. Live
+        // streams skip what arrived meanwhile to get back to the live edge
+        if in_replay && replaying.is_empty() {
+            if live {
+                jump_to_live(&mut video, &mut timeshift, &mut frames);
+            } else {
+                let current_time = *frames_seen.read().await as f32 / video.fps as f32;
+
+                video.start_audio(current_time);
+
+                video.start_captions(current_time);
+            }
+
+            pacer.reset();
+        }
+
+        // Replayed frames don't move the playback position
+        let frame_number = if in_replay {
+            (pts * video.fps as f64) as u64
+        } else {
+            let mut frames_seen_write_lock = frames_seen.write().await;
+
+            *frames_seen_write_lock += 1;
+
+            let frame_number = *frames_seen_write_lock;
+
+            drop(frames_seen_write_lock);
+
+            stats.set_position(frame_number as f32 / video.fps as f32);
+
+            frame_number
+        };
+
+        // Reached B, go round again from A
+        if let Some(a) = ab_loop
+            .restart_at(frame_number as f32 / video.fps as f32)
+            .filter(|_| !in_replay)
+        {
+            seek(&mut video, &seek_tx, &mut frames, &frames_seen, a, true).await;
+            replay_buffer.clear();
+            pacer.reset();
+            continue;
+        }
+
+        if let Some(clock_sync) = clock_sync.as_ref().filter(|_| !in_replay) {
+            let period = video.loop_period(duration).map(|period| period as f64);
+
+            match clock_sync.correct(frame_number as f64 / video.fps as f64, period) {
+                ClockCorrection::OnTime => {}
+                ClockCorrection::Wait(secs) => {
+                    tokio::time::sleep(Duration::from_secs_f64(secs)).await;
+                }
+                ClockCorrection::Drop => continue,
+                ClockCorrection::Seek(position) => {
+                    seek(
+                        &mut video,
+                        &seek_tx,
+                        &mut frames,
+                        &frames_seen,
+                        position,
+                        true,
+                    )
+                    .await;
+                    replay_buffer.clear();
+                    pacer.reset();
+                    continue;
+                }
+            }
+        }
+
+        // Replays don't move the audio, it waits for them to finish
+        if !in_replay && last_audio_sync.elapsed() >= AUDIO_SYNC_INTERVAL {
+            if let Some(external_audio) = &video.external_audio {
+                external_audio
+                    .lock()
+                    .unwrap()
+                    .keep_in_sync(frame_number as f32 / video.fps as f32);
+            }
+
+            last_audio_sync = Instant::now();
+        }
+
+        // Status bars only need the position about once a second
+        if last_now_playing.is_none_or(|at| at.elapsed() >= Duration::from_secs(1)) {
+            video.write_now_playing("playing", duration);
+            last_now_playing = Some(Instant::now());
+        }
+
+        if let Some(interval) = video.hooks.frame_interval() {
+            if last_frame_hook.elapsed() >= interval {
+                video.hooks.run_frame(&video.hook_state("frame", "playing"));
+                last_frame_hook = Instant::now();
+            }
+        }
+
+        // Keep time without drawing frames nobody is going to see
+        if !video.should_render(frame_number, focused) {
+            tokio::time::sleep(std_frame_time).await;
+            continue;
+        }
+
+        // Output can't keep up, drop frames rather than drift behind the audio
+        if !video.remove_fps_cap && pacer.should_drop() {
+            continue;
+        }
+
+        // The ghost trail effect compares against a frame from a moment ago
+        video.trail_reference = video
+            .trail_frames()
+            .and_then(|frames| replay_buffer.back(frames))
+            .map(|(frame, ..)| frame.clone());
+
+        if video.capabilities.synchronized_output {
+            queue!(stdout, BeginSynchronizedUpdate)?;
+        }
 
         video.write_header(&mut stdout)?;
 
         let start = Instant::now();
 
-        video.write_frame(&frame, &mut stdout)?;
+        video.write_frame(&frame, pts, &mut stdout)?;
+        shown = Some((frame, pts));
+
+        if !video.no_ui {
+            video.write_seek_bar(
+                &mut stdout,
+                frame_number as f32 / video.fps as f32,
+                duration,
+            )?;
+            video.write_intro_prompt(&mut stdout, frame_number as f32 / video.fps as f32)?;
+            video.write_scope(&mut stdout)?;
+            video.write_captions(&mut stdout, pts)?;
+            video.write_osd(&mut stdout)?;
+        }
 
         let elapsed = start.elapsed();
-        let sleep_duration = std_frame_time.saturating_sub(elapsed);
+        let sleep_duration = pacer.sleep_for(elapsed);
 
         // Wait if necessary to maintain the target FPS with a preloaded video
         if !video.remove_fps_cap {
             tokio::time::sleep(sleep_duration).await;
         }
 
+        if video.capabilities.synchronized_output {
+            queue!(stdout, EndSynchronizedUpdate)?;
+        }
+
+        let flush_start = Instant::now();
+
         stdout.flush().unwrap();
 
-        frame_times.push(Instant::now());
+        let now = Instant::now();
+        let mut output_time = now - flush_start;
+
+        stats.record_frame(
+            elapsed + (now - flush_start),
+            now - frame_times.last().copied().unwrap_or(start),
+            std_frame_time,
+        );
+
+        frame_times.push(now);
 
         let render_fps = calculate_fps(&frame_times);
+        stats.set_render_fps(render_fps);
 
         if frame_times.len() > 10 {
             frame_times = frame_times[frame_times.len() - 10..].to_vec();
         }
 
-        let frames_seen = frames_seen.read().await;
-
-        let current_time = *frames_seen as f32 / video.fps as f32;
-
-        drop(frames_seen);
+        let current_time = *frames_seen.read().await as f32 / video.fps as f32;
 
         if !video.fullscreen {
             video.write_footer(
@@ -258,16 +1242,35 @@ async fn handle_render(
             )?;
         }
 
+        let footer_flush_start = Instant::now();
+
         stdout.flush().unwrap();
 
-        if let DurationType::Fixed(duration) = duration {
-            if (duration as f32 - current_time) < 0.05 {
-                end();
+        output_time += footer_flush_start.elapsed();
+        pacer.record(elapsed, output_time);
+
+        if let Some(end) = video.end_of_clip(duration) {
+            if (end - current_time) < 0.05 {
+                // Kiosk decoders only start over by themselves at the end of
+                // the video, an --end mark before that is looped like --loop
+                if !video.kiosk
+                    || matches!(duration, DurationType::Fixed(duration) if end < duration as f32)
+                {
+                    at_end = true;
+                    continue;
+                }
+
+                // The decoder has already started over from --start
+                *frames_seen.write().await = (video.clip_start * video.fps as f32) as u64;
+
+                video.start_audio(video.clip_start);
+
+                video.start_captions(video.clip_start);
             }
         }
     }
 
-    end();
+    end(&teardown);
 
     Ok(())
 }
@@ -0,0 +1,115 @@
+#[cfg(feature = "metrics")]
+use anyhow::Context;
+#[cfg(feature = "metrics")]
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+#[cfg(feature = "metrics")]
+use std::time::Duration;
+#[cfg(feature = "metrics")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "metrics")]
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::stats::Stats;
+
+// Longest a client gets to send its request before it's answered anyway
+#[cfg(feature = "metrics")]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[cfg(not(feature = "metrics"))]
+pub async fn serve_metrics(_addr: &str, _stats: Arc<Stats>) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "Metrics aren't supported by this build, it was built without the metrics feature"
+    )
+}
+
+// Answer every request on `addr` with the current metrics, whatever the path.
+// Scrapers only ever ask for one thing, so there's no need for a real server
+#[cfg(feature = "metrics")]
+pub async fn serve_metrics(addr: &str, stats: Arc<Stats>) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to serve metrics on {}", addr))?;
+
+    loop {
+        let Ok((socket, _)) = listener.accept().await else {
+            continue;
+        };
+
+        // A slow or silent client can't hold up anyone else's scrape
+        tokio::spawn(respond(socket, stats.clone()));
+    }
+}
+
+#[cfg(feature = "metrics")]
+async fn respond(mut socket: TcpStream, stats: Arc<Stats>) {
+    // The request itself doesn't matter, it only needs reading so the client
+    // isn't reset before it gets the response
+    let mut request = [0u8; 1024];
+    let _ = tokio::time::timeout(REQUEST_TIMEOUT, socket.read(&mut request)).await;
+
+    let body = render_metrics(&stats);
+    let response = format!(
+        "HTTP/1.0 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
+
+// Prometheus text exposition format
+#[cfg(feature = "metrics")]
+fn render_metrics(stats: &Stats) -> String {
+    let position = stats.position();
+
+    let metrics = [
+        (
+            "window_fps",
+            "gauge",
+            "Frames rendered per second",
+            stats.render_fps(),
+        ),
+        (
+            "window_frames_total",
+            "counter",
+            "Frames rendered",
+            stats.frames.load(Ordering::Relaxed) as f64,
+        ),
+        (
+            "window_dropped_frames_total",
+            "counter",
+            "Frames that took longer to render than they're shown for",
+            stats.dropped_frames.load(Ordering::Relaxed) as f64,
+        ),
+        (
+            "window_buffer_seconds",
+            "gauge",
+            "Seconds decoded ahead of playback",
+            stats.network_health(position).buffer.max(0.0) as f64,
+        ),
+        (
+            "window_bytes_out_total",
+            "counter",
+            "Bytes written to the terminal",
+            stats.bytes_out.load(Ordering::Relaxed) as f64,
+        ),
+        (
+            "window_position_seconds",
+            "gauge",
+            "Playback position",
+            position as f64,
+        ),
+    ];
+
+    metrics
+        .iter()
+        .map(|(name, kind, help, value)| {
+            format!(
+                "# HELP {} {}\n# TYPE {} {}\n{} {}\n",
+                name, help, name, kind, name, value
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,165 @@
+use anyhow::Context;
+use clap::{Parser, ValueEnum};
+use crossterm::cursor::{self, MoveTo};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Print, ResetColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use image::{ImageFormat, RgbImage};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::env_defaults;
+use crate::layout::truncate;
+use crate::utils::args::{value_name, Args, CharacterMode, ColorDepth, ModesArgs};
+use crate::video::{frame_image, request_seek, Video};
+
+// Widest a tile gets relative to its height in cells, a 16:9 picture in cells
+// about twice as tall as they're wide
+const TILE_ASPECT: f32 = 32.0 / 9.0;
+
+// `window modes`, the same frame in a grid of every character mode
+pub async fn run_modes(args: ModesArgs) -> anyhow::Result<()> {
+    let still = still_frame(&args.input, args.at).await?;
+
+    let depth = args.color_depth.unwrap_or_else(env_defaults::color_depth);
+
+    let mut stdout = io::stdout();
+
+    execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+    terminal::enable_raw_mode()?;
+
+    let result = show_grid(&mut stdout, &still, depth);
+
+    terminal::disable_raw_mode()?;
+    execute!(stdout, ResetColor, cursor::Show, LeaveAlternateScreen)?;
+
+    result
+}
+
+// Redraw the grid until quit, in the next color depth with c
+fn show_grid(stdout: &mut impl Write, still: &RgbImage, depth: ColorDepth) -> anyhow::Result<()> {
+    let depths = ColorDepth::value_variants();
+    let mut current = depths.iter().position(|d| *d == depth).unwrap_or(0);
+
+    loop {
+        draw_grid(stdout, still, &depths[current])?;
+
+        match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::Char('c') => current = (current + 1) % depths.len(),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+// An image file as it is, or a video's frame at `at` seconds
+async fn still_frame(input: &str, at: f32) -> anyhow::Result<RgbImage> {
+    if Path::new(input).is_file() && ImageFormat::from_path(input).is_ok() {
+        return Ok(image::open(input)
+            .with_context(|| format!("Failed to open image {}", input))?
+            .to_rgb8());
+    }
+
+    let mut video = Video::from_args(Args::try_parse_from(["window", "--no-audio", input])?)?;
+    let (mut frames, seek_tx) = video.fetch_video(video.hw_accel.clone()).await?;
+
+    if at > 0.0 {
+        (_, frames) = request_seek(&seek_tx, at)
+            .await
+            .context("The video ended before it could seek")?;
+    }
+
+    let (frame, ..) = frames
+        .recv()
+        .await
+        .with_context(|| format!("No frame at {}s in {}", at, input))?;
+
+    frame_image(frame)
+}
+
+// As many columns of tiles as give the largest pictures
+fn grid_columns(tiles: usize, (width, height): (u16, u16)) -> usize {
+    (1..=tiles)
+        .max_by(|&a, &b| {
+            let picture = |columns: usize| {
+                let tile_width = width as f32 / columns as f32;
+                let tile_height = height as f32 / tiles.div_ceil(columns) as f32;
+
+                tile_width.min(tile_height * TILE_ASPECT)
+            };
+
+            picture(a).total_cmp(&picture(b))
+        })
+        .unwrap_or(1)
+}
+
+fn draw_grid(stdout: &mut impl Write, still: &RgbImage, depth: &ColorDepth) -> anyhow::Result<()> {
+    let modes: Vec<String> = CharacterMode::value_variants()
+        .iter()
+        .filter(|mode| !matches!(mode, CharacterMode::Auto))
+        .map(value_name)
+        .collect();
+    let depth = value_name(depth);
+
+    let (width, height) = terminal::size()?;
+    // The bottom row is for the key hints
+    let grid_height = height.saturating_sub(1);
+
+    let columns = grid_columns(modes.len(), (width, grid_height));
+    let tile_width = width / columns as u16;
+    let tile_height = grid_height / modes.len().div_ceil(columns) as u16;
+
+    queue!(stdout, ResetColor, Clear(ClearType::All))?;
+
+    for (i, mode) in modes.iter().enumerate() {
+        let column = (i % columns) as u16 * tile_width;
+        let row = (i / columns) as u16 * tile_height;
+
+        let mut video = Video::from_args(Args::try_parse_from([
+            "window",
+            "--mode",
+            mode,
+            "--color-depth",
+            &depth,
+            "--fullscreen",
+            "--no-audio",
+            "still",
+        ])?)?;
+
+        // A row for the label and a column between tiles
+        video.viewport = Some((
+            column,
+            row + 1,
+            tile_width.saturating_sub(1),
+            tile_height.saturating_sub(1),
+        ));
+
+        let frame = video.fit_still(still)?;
+
+        video.write_frame(&frame, 0.0, stdout)?;
+
+        queue!(
+            stdout,
+            ResetColor,
+            MoveTo(column, row),
+            Print(truncate(mode, tile_width.saturating_sub(1) as usize))
+        )?;
+    }
+
+    queue!(
+        stdout,
+        ResetColor,
+        MoveTo(0, height.saturating_sub(1)),
+        Print(truncate(
+            &format!("Colors: {}  c to change, q to quit", depth),
+            width as usize
+        ))
+    )?;
+    stdout.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+// How much of a cell's motion score carries over to the next frame, so a region
+// that keeps moving stays ahead of one that changed once
+const MOTION_DECAY: f32 = 0.5;
+
+// Priority a deferred cell gains for every frame it waits, so static background
+// still catches up eventually instead of going stale behind constant motion
+const DEFERRED_PRIORITY: f32 = 24.0;
+
+// Per-cell motion scores for picking which changed cells to redraw when only a
+// limited number of cells can be updated each frame
+#[derive(Default)]
+pub struct MotionMap {
+    scores: Vec<f32>,
+    deferred: Vec<u32>,
+}
+
+impl MotionMap {
+    // Forget everything when the grid changes shape, the cells no longer line up
+    pub fn resize(&mut self, cells: usize) {
+        if self.scores.len() != cells {
+            self.scores = vec![0.0; cells];
+            self.deferred = vec![0; cells];
+        }
+    }
+
+    // Record how far a cell moved since the last frame
+    pub fn record(&mut self, cell: usize, distance: f32) {
+        self.scores[cell] = self.scores[cell] * MOTION_DECAY + distance;
+    }
+
+    // Keep the `budget` changed cells with the most motion, returning the rest
+    // to be left as they are on screen until a later frame
+    pub fn schedule(&mut self, changed: &mut Vec<usize>, budget: usize) -> Vec<usize> {
+        if changed.len() <= budget {
+            changed.iter().for_each(|&cell| self.deferred[cell] = 0);
+            return Vec::new();
+        }
+
+        let priority =
+            |cell: usize| self.scores[cell] + self.deferred[cell] as f32 * DEFERRED_PRIORITY;
+
+        changed.sort_unstable_by(|&a, &b| priority(b).total_cmp(&priority(a)));
+
+        let deferred = changed.split_off(budget);
+
+        changed.iter().for_each(|&cell| self.deferred[cell] = 0);
+        deferred.iter().for_each(|&cell| self.deferred[cell] += 1);
+
+        // Back to screen order so runs of the same color still share escape codes
+        changed.sort_unstable();
+
+        deferred
+    }
+}
@@ -0,0 +1,33 @@
+use serde::Serialize;
+use std::fs;
+
+// What's playing, written as JSON for status bars to poll
+#[derive(Serialize)]
+pub struct NowPlaying<'a> {
+    pub title: &'a str,
+    pub artist: Option<&'a str>,
+    pub position: f32,
+    // None for live streams
+    pub duration: Option<u64>,
+    pub state: &'a str,
+    // Thumbnail URL when the source has one
+    pub artwork: Option<&'a str>,
+}
+
+// Write through a temporary file and rename it over the old one, so a poll
+// never reads a half written file. Failures are ignored, like hook failures
+pub fn write_now_playing(path: &str, now_playing: &NowPlaying) {
+    let Ok(json) = serde_json::to_string(now_playing) else {
+        return;
+    };
+
+    let temp_path = format!("{}.tmp", path);
+
+    if fs::write(&temp_path, json).is_ok() {
+        let _ = fs::rename(&temp_path, path);
+    }
+}
+
+pub fn remove_now_playing(path: &str) {
+    let _ = fs::remove_file(path);
+}
@@ -0,0 +1,79 @@
+use std::io::{self, Write};
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+
+use crate::ttyrec::Ttyrec;
+
+// Flushed frames waiting for the output thread before flushing blocks
+const QUEUED_FRAMES: usize = 2;
+
+// Held by the output thread while it writes, and by teardown so nothing
+// queued gets written over the restored terminal
+static OUTPUT_LOCK: Mutex<()> = Mutex::new(());
+
+pub fn lock_output() -> MutexGuard<'static, ()> {
+    OUTPUT_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// Buffers everything written and hands it to a dedicated thread on flush, so
+// a slow terminal blocks that thread instead of a runtime worker. Flushing
+// only waits once a couple of frames are already queued, and then lets the
+// runtime move its other tasks off the worker first
+pub struct OutputThread {
+    pending: Vec<u8>,
+    tx: SyncSender<Vec<u8>>,
+}
+
+impl OutputThread {
+    pub fn spawn<W: Write + Send + 'static>(
+        mut inner: W,
+        ttyrec: Option<Arc<Mutex<Ttyrec>>>,
+    ) -> Self {
+        let (tx, rx) = sync_channel::<Vec<u8>>(QUEUED_FRAMES);
+
+        thread::spawn(move || {
+            for buffer in rx {
+                let _output = lock_output();
+
+                // Nowhere left to report a broken terminal to
+                let _ = inner.write_all(&buffer);
+                let _ = inner.flush();
+
+                if let Some(ttyrec) = &ttyrec {
+                    ttyrec.lock().unwrap().record(&buffer);
+                }
+            }
+        });
+
+        Self {
+            pending: Vec::new(),
+            tx,
+        }
+    }
+}
+
+impl Write for OutputThread {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let stopped = || io::Error::new(io::ErrorKind::BrokenPipe, "output thread stopped");
+
+        match self.tx.try_send(std::mem::take(&mut self.pending)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(buffer)) => {
+                tokio::task::block_in_place(|| self.tx.send(buffer)).map_err(|_| stopped())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(stopped()),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+// Weight of the newest flush when smoothing the output latency, out of 8
+const LATENCY_WEIGHT: u32 = 2;
+
+// Keeps frames on the video's clock when writing to the terminal is slow, e.g.
+// over SSH. The sleep before flushing leaves room for how long flushing has
+// been taking, and time spent past the frame time is paid back by dropping
+// frames instead of piling up as delay
+pub struct Pacer {
+    frame_time: Duration,
+    output_latency: Duration,
+    behind: Duration,
+}
+
+impl Pacer {
+    pub fn new(frame_time: Duration) -> Self {
+        Self {
+            frame_time,
+            output_latency: Duration::ZERO,
+            behind: Duration::ZERO,
+        }
+    }
+
+    // How long to wait before flushing a frame that took `render_time` to draw
+    pub fn sleep_for(&self, render_time: Duration) -> Duration {
+        self.frame_time
+            .saturating_sub(render_time + self.output_latency)
+    }
+
+    // Record how long drawing and flushing a frame took in total
+    pub fn record(&mut self, render_time: Duration, output_time: Duration) {
+        self.output_latency =
+            (self.output_latency * (8 - LATENCY_WEIGHT) + output_time * LATENCY_WEIGHT) / 8;
+        self.behind += (render_time + output_time).saturating_sub(self.frame_time);
+    }
+
+    // Whether to drop the next frame, once a whole frame time has been lost
+    pub fn should_drop(&mut self) -> bool {
+        if self.behind < self.frame_time {
+            return false;
+        }
+
+        self.behind -= self.frame_time;
+
+        true
+    }
+
+    // Playback restarts from here after a pause or seek, nothing to catch up on
+    pub fn reset(&mut self) {
+        self.behind = Duration::ZERO;
+    }
+}
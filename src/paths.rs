@@ -0,0 +1,53 @@
+use std::env;
+use std::path::PathBuf;
+
+// Where window keeps its files, following each platform's conventions: XDG on
+// Linux and other unixes, ~/Library on macOS and %APPDATA% on Windows
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn home() -> Option<PathBuf> {
+    env_dir("HOME")
+}
+
+// Settings the user edits and keeps
+fn config_home() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env_dir("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env_dir("XDG_CONFIG_HOME").or_else(|| home().map(|home| home.join(".config")))
+    }
+}
+
+// Files that are safe to delete, they only save redoing work
+fn cache_home() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env_dir("LOCALAPPDATA")
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Caches"))
+    } else {
+        env_dir("XDG_CACHE_HOME").or_else(|| home().map(|home| home.join(".cache")))
+    }
+}
+
+// The config file, unless --config points elsewhere
+pub fn config_file(custom: Option<&str>) -> Option<PathBuf> {
+    match custom {
+        Some(path) => Some(PathBuf::from(path)),
+        None => config_home().map(|dir| dir.join("window").join("config")),
+    }
+}
+
+// The cache directory, unless --cache-dir points elsewhere
+pub fn cache_dir(custom: Option<&str>) -> Option<PathBuf> {
+    match custom {
+        Some(path) => Some(PathBuf::from(path)),
+        None => cache_home().map(|dir| dir.join("window")),
+    }
+}
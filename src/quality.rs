@@ -0,0 +1,54 @@
+use std::str::FromStr;
+
+// Quality steps from smoothest to sharpest as (pixel clear distance, every nth
+// subpixel of a cell compared against the last frame). The low end skips more
+// small changes and looks at fewer subpixels for them, the high end compares
+// every subpixel and redraws nearly every change
+pub const QUALITY_LEVELS: [(u16, u32); 6] = [(32, 4), (16, 2), (8, 2), (4, 1), (2, 1), (1, 1)];
+
+pub enum QualityChange {
+    Up,
+    Down,
+    // A level from 1 to the number of levels
+    Set(usize),
+}
+
+impl QualityChange {
+    pub fn apply(&self, level: usize) -> usize {
+        match self {
+            QualityChange::Up => (level + 1).min(QUALITY_LEVELS.len() - 1),
+            QualityChange::Down => level.saturating_sub(1),
+            QualityChange::Set(level) => level.saturating_sub(1).min(QUALITY_LEVELS.len() - 1),
+        }
+    }
+}
+
+// The level a clear distance falls into
+pub fn quality_level(pixel_clear_distance: u16) -> usize {
+    QUALITY_LEVELS
+        .iter()
+        .position(|&(distance, _)| distance <= pixel_clear_distance)
+        .unwrap_or(QUALITY_LEVELS.len() - 1)
+}
+
+impl FromStr for QualityChange {
+    type Err = String;
+
+    // Accepts "+", "-" or a level
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "+" => Ok(QualityChange::Up),
+            "-" => Ok(QualityChange::Down),
+            level => match level.parse::<usize>() {
+                Ok(level) if (1..=QUALITY_LEVELS.len()).contains(&level) => {
+                    Ok(QualityChange::Set(level))
+                }
+                _ => Err(format!(
+                    "invalid quality: {} (expected +, - or 1-{})",
+                    s,
+                    QUALITY_LEVELS.len()
+                )),
+            },
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use anyhow::Context;
+use std::path::PathBuf;
+use video_rs::io::Reader;
+use video_rs::{Muxer, MuxerBuilder, Packet, Writer};
+
+// Remuxes the video stream's packets into a file as they're read, without re-encoding
+pub struct Recorder {
+    muxer: Muxer<Writer>,
+    finished: bool,
+}
+
+impl Recorder {
+    pub fn new(path: &str, reader: &Reader, stream_index: usize) -> anyhow::Result<Self> {
+        let writer = Writer::new(PathBuf::from(path))
+            .with_context(|| format!("Failed to create recording {}", path))?;
+
+        let muxer = MuxerBuilder::new(writer)
+            .with_stream(reader.stream_info(stream_index)?)?
+            .build();
+
+        Ok(Self {
+            muxer,
+            finished: false,
+        })
+    }
+
+    // Packets the container rejects, like ones going back in time after a seek, are skipped
+    pub fn record(&mut self, packet: Packet) {
+        if !self.finished {
+            let _ = self.muxer.mux(packet);
+        }
+    }
+
+    // Write the trailer, formats like mp4 can't be played back without it
+    pub fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            let _ = self.muxer.finish();
+        }
+    }
+}
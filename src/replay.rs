@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+
+use crate::video::DecodedFrame;
+
+// Seconds of recent playback kept for instant replay
+pub const REPLAY_SECS: u64 = 10;
+
+// Rolling store of the frames last shown, already decoded at the size they're
+// drawn at so replaying them costs no decoding
+pub struct ReplayBuffer {
+    frames: VecDeque<DecodedFrame>,
+    capacity: usize,
+}
+
+impl ReplayBuffer {
+    pub fn new(fps: u64) -> Self {
+        let capacity = (fps * REPLAY_SECS) as usize;
+
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, frame: &DecodedFrame) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+
+        self.frames.push_back(frame.clone());
+    }
+
+    // The frame shown `frames` frames before the latest one
+    pub fn back(&self, frames: usize) -> Option<&DecodedFrame> {
+        let index = self.frames.len().checked_sub(frames + 1)?;
+
+        self.frames.get(index)
+    }
+
+    // Hand over everything buffered, oldest first
+    pub fn take(&mut self) -> VecDeque<DecodedFrame> {
+        std::mem::take(&mut self.frames)
+    }
+
+    // Frames from before a seek aren't the last few seconds anymore
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+}
@@ -0,0 +1,125 @@
+use image::{ImageBuffer, Rgb};
+
+use crate::utils::get_grey::get_grey;
+
+// Size in cells of the scope drawn in the corner of the video
+pub const SCOPE_WIDTH: usize = 32;
+pub const SCOPE_HEIGHT: usize = 8;
+
+// Only every nth pixel along each axis is counted, plenty for a rough picture
+const SAMPLE_STEP: usize = 4;
+
+// Eighth blocks for the histogram bars, empty to full
+const BAR_CHARS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Shades for the parade, from no pixels at that level to the most
+const DENSITY_CHARS: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+// Scopes cycled through at runtime, starting from none
+pub const SCOPE_CYCLE: [Option<Scope>; 3] = [None, Some(Scope::Histogram), Some(Scope::Parade)];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    // Luma histogram, dark to bright from left to right
+    Histogram,
+    // Red, green and blue side by side, each plotting level against position across the frame
+    Parade,
+}
+
+// Rows of text to draw for the scope, top to bottom, each with the channel it
+// belongs to for coloring (0 red, 1 green, 2 blue, None for luma)
+pub fn render_scope(
+    scope: Scope,
+    img: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+) -> Vec<Vec<(char, Option<usize>)>> {
+    match scope {
+        Scope::Histogram => render_histogram(img),
+        Scope::Parade => render_parade(img),
+    }
+}
+
+fn samples(img: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> impl Iterator<Item = (u32, &Rgb<u8>)> {
+    img.enumerate_pixels()
+        .filter(|(x, y, _)| {
+            (*x as usize).is_multiple_of(SAMPLE_STEP) && (*y as usize).is_multiple_of(SAMPLE_STEP)
+        })
+        .map(|(x, _, pixel)| (x, pixel))
+}
+
+fn render_histogram(img: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> Vec<Vec<(char, Option<usize>)>> {
+    let mut bins = [0u32; SCOPE_WIDTH];
+
+    for (_, pixel) in samples(img) {
+        let grey = get_grey(pixel[0], pixel[1], pixel[2]) as usize;
+
+        bins[grey * SCOPE_WIDTH / 256] += 1;
+    }
+
+    let max = bins.iter().copied().max().unwrap_or(0).max(1);
+    let eighths = SCOPE_HEIGHT * 8;
+
+    (0..SCOPE_HEIGHT)
+        .map(|row| {
+            // Eighths of a cell already filled by the rows below this one
+            let below = (SCOPE_HEIGHT - 1 - row) * 8;
+
+            bins.iter()
+                .map(|&count| {
+                    let height = count as usize * eighths / max as usize;
+
+                    (BAR_CHARS[height.saturating_sub(below).min(8)], None)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn render_parade(img: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> Vec<Vec<(char, Option<usize>)>> {
+    // Each channel gets a third of the width, less a column between them
+    let channel_width = (SCOPE_WIDTH - 2) / 3;
+    let mut counts = vec![[[0u32; SCOPE_HEIGHT]; 3]; channel_width];
+
+    for (x, pixel) in samples(img) {
+        let column = x as usize * channel_width / img.width().max(1) as usize;
+
+        for (channel, &value) in pixel.0.iter().enumerate() {
+            counts[column][channel][value as usize * SCOPE_HEIGHT / 256] += 1;
+        }
+    }
+
+    let max = counts
+        .iter()
+        .flat_map(|column| column.iter().flatten())
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    (0..SCOPE_HEIGHT)
+        .map(|row| {
+            // Bright levels at the top
+            let level = SCOPE_HEIGHT - 1 - row;
+            let mut line = Vec::with_capacity(SCOPE_WIDTH);
+
+            for channel in 0..3 {
+                if channel > 0 {
+                    line.push((' ', None));
+                }
+
+                for column in &counts {
+                    let count = column[channel][level] as usize;
+
+                    // Any pixels at all show up, however few
+                    let density = match count {
+                        0 => 0,
+                        count => (count * (DENSITY_CHARS.len() - 1) / max as usize).max(1),
+                    };
+
+                    line.push((DENSITY_CHARS[density], Some(channel)));
+                }
+            }
+
+            line
+        })
+        .collect()
+}
@@ -0,0 +1,191 @@
+use anyhow::Context;
+use clap::{Parser, ValueEnum};
+use crossterm::cursor::{self, MoveTo};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Print, ResetColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::capabilities::Capabilities;
+use crate::env_defaults;
+use crate::utils::args::{value_name, Args, CharacterMode};
+use crate::video::{Frame, Video};
+
+// Size of the test pattern before it's fitted to the terminal, 16:9 like most video
+const PATTERN_SIZE: (u32, u32) = (1600, 900);
+
+// Offered on the first run, when there's no config yet. Looks at what the
+// terminal supports, lets the user compare the character modes on a test
+// pattern and writes the config. Declining writes an empty config, so it's
+// only ever offered once
+pub fn run_setup(path: &Path) -> anyhow::Result<String> {
+    print!(
+        "No config found at {}, set window up for this terminal? [Y/n] ",
+        path.display()
+    );
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    let mut config =
+        "# window config, one flag per line as typed on the command line\n".to_string();
+
+    if !answer.trim().to_lowercase().starts_with('n') {
+        print_capabilities();
+
+        println!("Press enter to compare the character modes");
+        io::stdin().lock().read_line(&mut String::new())?;
+
+        if let Some(mode) = pick_mode()? {
+            config.push_str(&format!("--mode {}\n", mode));
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, &config)
+        .with_context(|| format!("Failed to write config {}", path.display()))?;
+
+    println!("Saved {}", path.display());
+
+    Ok(config)
+}
+
+fn print_capabilities() {
+    let capabilities = Capabilities::detect();
+    let yes_no = |supported: bool| if supported { "yes" } else { "no" };
+
+    println!();
+    println!("This terminal");
+    println!(
+        "  Colors:                {}",
+        value_name(&env_defaults::color_depth())
+    );
+    println!(
+        "  Cell size:             {}",
+        capabilities
+            .cell_size
+            .map_or("unknown".to_string(), |(width, height)| format!(
+                "{}x{} pixels",
+                width, height
+            ))
+    );
+    println!(
+        "  Synchronized output:   {}",
+        yes_no(capabilities.synchronized_output)
+    );
+    println!();
+}
+
+// Show the test pattern in one mode after another until one is picked, or
+// none with escape
+fn pick_mode() -> anyhow::Result<Option<String>> {
+    let modes: Vec<String> = CharacterMode::value_variants()
+        .iter()
+        .filter(|mode| !matches!(mode, CharacterMode::Auto))
+        .map(value_name)
+        .collect();
+
+    let mut stdout = io::stdout();
+
+    execute!(stdout, EnterAlternateScreen, cursor::Hide)?;
+    terminal::enable_raw_mode()?;
+
+    let picked = preview_modes(&mut stdout, &modes);
+
+    terminal::disable_raw_mode()?;
+    execute!(stdout, ResetColor, cursor::Show, LeaveAlternateScreen)?;
+
+    picked
+}
+
+fn preview_modes(stdout: &mut impl Write, modes: &[String]) -> anyhow::Result<Option<String>> {
+    let mut current = 0;
+
+    loop {
+        draw_preview(stdout, &modes[current])?;
+
+        match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::Left | KeyCode::Char('h') => {
+                    current = (current + modes.len() - 1) % modes.len();
+                }
+                KeyCode::Right | KeyCode::Char('l') => current = (current + 1) % modes.len(),
+                KeyCode::Enter => return Ok(Some(modes[current].clone())),
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+// The test pattern drawn by the renderer as it'd draw video in that mode
+fn draw_preview(stdout: &mut impl Write, mode: &str) -> anyhow::Result<()> {
+    let args = Args::try_parse_from(["window", "--mode", mode, "--no-audio", "test-pattern"])?;
+    let mut video = Video::from_args(args)?;
+
+    let (width, height) = video.still_size(PATTERN_SIZE);
+    let pattern = test_pattern(width as usize, height as usize);
+
+    queue!(stdout, ResetColor, Clear(ClearType::All))?;
+    video.write_frame(&pattern, 0.0, stdout)?;
+
+    let (_, rows) = terminal::size()?;
+
+    queue!(
+        stdout,
+        ResetColor,
+        MoveTo(0, rows.saturating_sub(1)),
+        Print(format!(
+            "{}  ← → to compare, enter to keep, esc to skip",
+            mode
+        ))
+    )?;
+    stdout.flush()?;
+
+    Ok(())
+}
+
+// Hue across, getting darker downwards, over a grey ramp along the bottom.
+// A ring in the middle shows whether round things stay round
+fn test_pattern(width: usize, height: usize) -> Frame {
+    let (pattern_width, pattern_height) = (PATTERN_SIZE.0 as f32, PATTERN_SIZE.1 as f32);
+
+    Frame::from_shape_fn((height, width, 3), |(y, x, channel)| {
+        let u = x as f32 / width.max(1) as f32;
+        let v = y as f32 / height.max(1) as f32;
+
+        let (dx, dy) = ((u - 0.5) * pattern_width, (v - 0.45) * pattern_height);
+        let ring = ((dx * dx + dy * dy).sqrt() - pattern_height / 3.0).abs() < 12.0;
+
+        if ring {
+            return 255;
+        }
+
+        if v >= 0.85 {
+            return (u * 255.0) as u8;
+        }
+
+        let brightness = 1.0 - v / 0.85 * 0.9;
+        let hue = u * 6.0;
+        let rising = hue.fract();
+
+        let (r, g, b) = match hue as u32 {
+            0 => (1.0, rising, 0.0),
+            1 => (1.0 - rising, 1.0, 0.0),
+            2 => (0.0, 1.0, rising),
+            3 => (0.0, 1.0 - rising, 1.0),
+            4 => (rising, 0.0, 1.0),
+            _ => (1.0, 0.0, 1.0 - rising),
+        };
+
+        ([r, g, b][channel] * brightness * 255.0) as u8
+    })
+}
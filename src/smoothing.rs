@@ -0,0 +1,41 @@
+// Change in a channel from its smoothed value past which the new value is
+// taken as is. Grain stays well under it, while smoothing real motion or a cut
+// would leave ghosts behind
+const MAX_SMOOTHED_CHANGE: f32 = 40.0;
+
+// Blends every pixel with its recent past, calming the frame to frame noise of
+// grainy sources that text modes show as flickering characters
+pub struct TemporalSmoothing {
+    // Weight of the past, 0.5 averages over roughly the last 3 frames
+    strength: f32,
+    history: Vec<f32>,
+}
+
+impl TemporalSmoothing {
+    pub fn new(strength: f32) -> Self {
+        Self {
+            strength,
+            history: Vec::new(),
+        }
+    }
+
+    // Smooth packed RGB pixels in place. A frame of another size starts over
+    pub fn apply(&mut self, pixels: &mut [u8]) {
+        if self.history.len() != pixels.len() {
+            self.history = pixels.iter().map(|&value| value as f32).collect();
+            return;
+        }
+
+        for (value, past) in pixels.iter_mut().zip(&mut self.history) {
+            let current = *value as f32;
+
+            *past = if (current - *past).abs() > MAX_SMOOTHED_CHANGE {
+                current
+            } else {
+                *past * self.strength + current * (1.0 - self.strength)
+            };
+
+            *value = past.round() as u8;
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::{Arc, Mutex};
+use video_rs::decode::DecoderSplit;
+use video_rs::io::Reader;
+use video_rs::{Decoder, Error, Frame, Time};
+
+use crate::recorder::Recorder;
+
+// Demuxing and decoding driven separately, so packets pass through our hands
+// before they reach the decoder
+pub struct Source {
+    decoder: DecoderSplit,
+    reader: Reader,
+    stream_index: usize,
+    draining: bool,
+    recorder: Option<Arc<Mutex<Recorder>>>,
+}
+
+impl Source {
+    pub fn new(decoder: Decoder) -> Self {
+        let (decoder, reader, stream_index) = decoder.into_parts();
+
+        Self {
+            decoder,
+            reader,
+            stream_index,
+            draining: false,
+            recorder: None,
+        }
+    }
+
+    // Carry on from a freshly opened decoder of the same input, e.g. after
+    // refreshing an expired URL. A running recording keeps going
+    pub fn reopen(&mut self, decoder: Decoder) {
+        let (decoder, reader, stream_index) = decoder.into_parts();
+
+        self.decoder = decoder;
+        self.reader = reader;
+        self.stream_index = stream_index;
+        self.draining = false;
+    }
+
+    // Tee every packet read from now on into a recording at `path`
+    pub fn record_to(&mut self, path: &str) -> anyhow::Result<Arc<Mutex<Recorder>>> {
+        let recorder = Arc::new(Mutex::new(Recorder::new(
+            path,
+            &self.reader,
+            self.stream_index,
+        )?));
+
+        self.recorder = Some(recorder.clone());
+
+        Ok(recorder)
+    }
+
+    // Same as `Decoder::decode`, read packets until one produces a frame
+    pub fn decode(&mut self) -> Result<(Time, Frame), Error> {
+        loop {
+            if self.draining {
+                return match self.decoder.drain() {
+                    Ok(Some(frame)) => Ok(frame),
+                    Ok(None) | Err(Error::ReadExhausted) => {
+                        self.decoder.reset();
+                        self.draining = false;
+                        Err(Error::DecodeExhausted)
+                    }
+                    Err(err) => Err(err),
+                };
+            }
+
+            match self.reader.read(self.stream_index) {
+                Ok(packet) => {
+                    if let Some(recorder) = &self.recorder {
+                        recorder.lock().unwrap().record(packet.clone());
+                    }
+
+                    if let Some(frame) = self.decoder.decode(packet)? {
+                        return Ok(frame);
+                    }
+                }
+                Err(Error::ReadExhausted) => self.draining = true,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    pub fn seek(&mut self, timestamp_milliseconds: i64) -> Result<(), Error> {
+        self.reader.seek(timestamp_milliseconds)?;
+        self.decoder.reset();
+        self.draining = false;
+
+        Ok(())
+    }
+
+    // Bytes read from the input so far, all streams and container overhead included
+    pub fn bytes_read(&self) -> u64 {
+        unsafe {
+            let pb = (*self.reader.input.as_ptr()).pb;
+
+            if pb.is_null() {
+                0
+            } else {
+                (*pb).bytes_read as u64
+            }
+        }
+    }
+}
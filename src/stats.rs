@@ -0,0 +1,362 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::utils::sanitize::sanitize;
+
+use tokio::sync::Notify;
+
+use crate::utils::cpu_time::process_cpu_time;
+
+// Upper bounds in milliseconds of the frame time histogram buckets,
+// anything slower lands in a final overflow bucket
+const HISTOGRAM_BUCKETS: [u64; 7] = [8, 16, 33, 50, 100, 250, 500];
+
+const HISTOGRAM_WIDTH: u64 = 40;
+
+// How long a warning stays on screen after it was last raised
+const WARNING_DURATION: Duration = Duration::from_secs(3);
+
+// Playback statistics shared between the decode task, the render loop and exit
+#[derive(Default)]
+pub struct Stats {
+    pub report: bool,
+    // Warnings go to stderr instead of the header
+    quiet: bool,
+    pub frames: AtomicU64,
+    pub dropped_frames: AtomicU64,
+    pub bytes_out: AtomicU64,
+    pub render_nanos: AtomicU64,
+    pub decoded_frames: AtomicU64,
+    pub decode_nanos: AtomicU64,
+    pub bytes_in: AtomicU64,
+    decoded_millis: AtomicU64,
+    played_millis: AtomicU64,
+    render_fps_millis: AtomicU64,
+    intro_end_millis: AtomicU64,
+    failed: AtomicBool,
+    exhausted: AtomicBool,
+    exhausted_notify: Notify,
+    last_frame_bytes: AtomicU64,
+    peak_frame_bytes: AtomicU64,
+    cells_drawn: AtomicU64,
+    cells_total: AtomicU64,
+    bitrate: AtomicU64,
+    bitrate_sample: Mutex<Option<(Instant, u64)>>,
+    warning: Mutex<Option<(String, Instant)>>,
+    cpu_sample: Mutex<Option<(Instant, Duration, Option<f32>)>>,
+    histogram: [AtomicU64; HISTOGRAM_BUCKETS.len() + 1],
+}
+
+impl Stats {
+    pub fn new(report: bool, quiet: bool) -> Self {
+        Self {
+            report,
+            quiet,
+            ..Default::default()
+        }
+    }
+
+    pub fn record_decode(&self, elapsed: Duration, position: f64, bytes_in: u64) {
+        self.decoded_frames.fetch_add(1, Ordering::Relaxed);
+        self.decode_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+        self.decoded_millis
+            .store((position * 1000.0) as u64, Ordering::Relaxed);
+        self.bytes_in.store(bytes_in, Ordering::Relaxed);
+
+        // Sample the bytes read about once a second to get the ingest bitrate
+        let mut sample = self.bitrate_sample.lock().unwrap();
+
+        match *sample {
+            Some((at, bytes)) if at.elapsed() >= Duration::from_secs(1) => {
+                let bitrate =
+                    bytes_in.saturating_sub(bytes) as f64 * 8.0 / at.elapsed().as_secs_f64();
+
+                self.bitrate.store(bitrate as u64, Ordering::Relaxed);
+                *sample = Some((Instant::now(), bytes_in));
+            }
+            Some(_) => {}
+            None => *sample = Some((Instant::now(), bytes_in)),
+        }
+    }
+
+    // Bytes written for the cells of a frame, and how many of its cells were redrawn
+    pub fn record_frame_output(&self, bytes: u64, cells_drawn: u64, cells_total: u64) {
+        self.last_frame_bytes.store(bytes, Ordering::Relaxed);
+        self.peak_frame_bytes.fetch_max(bytes, Ordering::Relaxed);
+        self.cells_drawn.fetch_add(cells_drawn, Ordering::Relaxed);
+        self.cells_total.fetch_add(cells_total, Ordering::Relaxed);
+    }
+
+    pub fn last_frame_bytes(&self) -> u64 {
+        self.last_frame_bytes.load(Ordering::Relaxed)
+    }
+
+    // Playback position in seconds, as last reported by the render loop
+    pub fn set_position(&self, position: f32) {
+        self.played_millis
+            .store((position.max(0.0) * 1000.0) as u64, Ordering::Relaxed);
+    }
+
+    pub fn position(&self) -> f32 {
+        self.played_millis.load(Ordering::Relaxed) as f32 / 1000.0
+    }
+
+    // Framerate the render loop is managing, averaged over the last few frames
+    pub fn set_render_fps(&self, fps: f64) {
+        self.render_fps_millis
+            .store((fps.max(0.0) * 1000.0) as u64, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn render_fps(&self) -> f64 {
+        self.render_fps_millis.load(Ordering::Relaxed) as f64 / 1000.0
+    }
+
+    pub fn set_intro_end(&self, position: f64) {
+        self.intro_end_millis
+            .store((position * 1000.0) as u64, Ordering::Relaxed);
+    }
+
+    // Where the content starts after the intro, once it has been detected
+    pub fn intro_end(&self) -> Option<f32> {
+        match self.intro_end_millis.load(Ordering::Relaxed) {
+            0 => None,
+            millis => Some(millis as f32 / 1000.0),
+        }
+    }
+
+    // Show a transient warning to the viewer for a few seconds
+    pub fn warn(&self, message: String) {
+        let message = sanitize(&message);
+
+        // Raw mode doesn't go back to the start of the line on a newline
+        if self.quiet {
+            eprint!("{}\r\n", message);
+            return;
+        }
+
+        *self.warning.lock().unwrap() = Some((message, Instant::now()));
+    }
+
+    // Warn about an error playback can't recover from
+    pub fn fail(&self, message: String) {
+        self.failed.store(true, Ordering::Relaxed);
+        self.warn(message);
+    }
+
+    pub fn failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    // Set by the decode task once it has sent the last frame, and cleared
+    // again by seeking away from the end
+    pub fn set_exhausted(&self, exhausted: bool) {
+        self.exhausted.store(exhausted, Ordering::Relaxed);
+
+        if exhausted {
+            self.exhausted_notify.notify_one();
+        }
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::Relaxed)
+    }
+
+    pub async fn wait_exhausted(&self) {
+        self.exhausted_notify.notified().await;
+    }
+
+    pub fn current_warning(&self) -> Option<String> {
+        match &*self.warning.lock().unwrap() {
+            Some((message, at)) if at.elapsed() < WARNING_DURATION => Some(message.clone()),
+            _ => None,
+        }
+    }
+
+    // Process CPU usage in percent of a single core, resampled about once a second
+    pub fn cpu_usage(&self) -> Option<f32> {
+        let mut sample = self.cpu_sample.lock().unwrap();
+
+        if let Some((at, _, usage)) = *sample {
+            if at.elapsed() < Duration::from_secs(1) {
+                return usage;
+            }
+        }
+
+        let cpu_time = process_cpu_time()?;
+        let usage = sample.map(|(at, last_cpu_time, _)| {
+            cpu_time.saturating_sub(last_cpu_time).as_secs_f32() / at.elapsed().as_secs_f32()
+                * 100.0
+        });
+
+        *sample = Some((Instant::now(), cpu_time, usage));
+
+        usage
+    }
+
+    pub fn network_health(&self, current_time: f32) -> NetworkHealth {
+        let decoded_position = self.decoded_millis.load(Ordering::Relaxed) as f32 / 1000.0;
+
+        NetworkHealth {
+            bitrate: self.bitrate.load(Ordering::Relaxed) as f64,
+            buffer: decoded_position - current_time,
+        }
+    }
+
+    // Record a rendered frame, `frame_time` being the time since the previous one
+    pub fn record_frame(&self, render_time: Duration, frame_time: Duration, budget: Duration) {
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        self.render_nanos
+            .fetch_add(render_time.as_nanos() as u64, Ordering::Relaxed);
+
+        // Rendering took longer than the frame is shown for, so playback fell behind
+        if render_time > budget {
+            self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let ms = frame_time.as_millis() as u64;
+        let bucket = HISTOGRAM_BUCKETS
+            .iter()
+            .position(|&max| ms < max)
+            .unwrap_or(HISTOGRAM_BUCKETS.len());
+
+        self.histogram[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn print_report(&self) {
+        let frames = self.frames.load(Ordering::Relaxed);
+        let dropped = self.dropped_frames.load(Ordering::Relaxed);
+        let bytes = self.bytes_out.load(Ordering::Relaxed);
+        let render_nanos = self.render_nanos.load(Ordering::Relaxed);
+        let decoded = self.decoded_frames.load(Ordering::Relaxed);
+        let decode_nanos = self.decode_nanos.load(Ordering::Relaxed);
+        let peak_frame_bytes = self.peak_frame_bytes.load(Ordering::Relaxed);
+        let cells_drawn = self.cells_drawn.load(Ordering::Relaxed);
+        let cells_total = self.cells_total.load(Ordering::Relaxed);
+
+        let per_frame = |total: u64, count: u64| total as f64 / count.max(1) as f64;
+        let share = |part: u64| part as f64 / (render_nanos + decode_nanos).max(1) as f64 * 100.0;
+
+        println!("Playback report");
+        println!("  Frames rendered:   {}", frames);
+        println!(
+            "  Dropped frames:    {} ({:.2}%)",
+            dropped,
+            per_frame(dropped, frames) * 100.0
+        );
+        println!(
+            "  Output per frame:  {:.1} KiB",
+            per_frame(bytes, frames) / 1024.0
+        );
+        println!(
+            "  Peak frame output: {:.1} KiB",
+            peak_frame_bytes as f64 / 1024.0
+        );
+        println!(
+            "  Cells redrawn:     {:.1}% ({:.1} bytes/cell)",
+            per_frame(cells_drawn, cells_total) * 100.0,
+            per_frame(bytes, cells_drawn)
+        );
+        println!(
+            "  Decode time:       {:.2}ms/frame ({:.0}%)",
+            per_frame(decode_nanos, decoded) / 1_000_000.0,
+            share(decode_nanos)
+        );
+        println!(
+            "  Render time:       {:.2}ms/frame ({:.0}%)",
+            per_frame(render_nanos, frames) / 1_000_000.0,
+            share(render_nanos)
+        );
+        println!();
+        println!("Frame time histogram");
+
+        let counts: Vec<u64> = self
+            .histogram
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .collect();
+        let max = counts.iter().copied().max().unwrap_or(0).max(1);
+
+        for (i, count) in counts.iter().enumerate() {
+            let label = match i {
+                0 => format!("<{}ms", HISTOGRAM_BUCKETS[0]),
+                i if i == HISTOGRAM_BUCKETS.len() => {
+                    format!(">={}ms", HISTOGRAM_BUCKETS[i - 1])
+                }
+                i => format!("{}-{}ms", HISTOGRAM_BUCKETS[i - 1], HISTOGRAM_BUCKETS[i]),
+            };
+
+            println!(
+                "  {:>10} |{:<width$}| {}",
+                label,
+                "#".repeat((count * HISTOGRAM_WIDTH / max) as usize),
+                count,
+                width = HISTOGRAM_WIDTH as usize
+            );
+        }
+    }
+}
+
+// Ingest bitrate and how far decoding is ahead of playback, for network sources
+#[derive(Clone, Copy)]
+pub struct NetworkHealth {
+    pub bitrate: f64,
+    pub buffer: f32,
+}
+
+// Wraps the terminal output to count the bytes that actually get written
+pub struct CountingWriter<W: Write> {
+    inner: W,
+    stats: Arc<Stats>,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W, stats: Arc<Stats>) -> Self {
+        Self { inner, stats }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.stats
+            .bytes_out
+            .fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Counts the bytes written through it, for keeping a frame within its byte budget
+pub struct ByteTally<'a, W: Write> {
+    inner: &'a mut W,
+    bytes: usize,
+}
+
+impl<'a, W: Write> ByteTally<'a, W> {
+    pub fn new(inner: &'a mut W) -> Self {
+        Self { inner, bytes: 0 }
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl<W: Write> Write for ByteTally<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{bail, Context};
+use std::fs;
+
+// A subtitle shown from `start` until `end`, in seconds
+struct Cue {
+    start: f64,
+    end: f64,
+    text: String,
+}
+
+// Subtitles from an SRT or ASS file. Cues are looked up by the timestamp of
+// the frame on screen, so they stay in step through seeks and replays
+pub struct Subtitles {
+    cues: Vec<Cue>,
+}
+
+impl Subtitles {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read subtitles {}", path))?;
+        let contents = contents.trim_start_matches('\u{FEFF}');
+
+        let cues = if contents.contains("[Events]") {
+            parse_ass(contents)
+        } else {
+            parse_srt(contents)
+        };
+
+        if cues.is_empty() {
+            bail!("No subtitles found in {}", path);
+        }
+
+        Ok(Self { cues })
+    }
+
+    // Text of the cues showing at a position, overlapping ones on separate lines
+    pub fn cue_at(&self, position: f64) -> Option<String> {
+        let text: Vec<&str> = self
+            .cues
+            .iter()
+            .filter(|cue| cue.start <= position && position < cue.end)
+            .map(|cue| cue.text.as_str())
+            .collect();
+
+        (!text.is_empty()).then(|| text.join("\n"))
+    }
+}
+
+// H:MM:SS followed by milliseconds after a comma (SRT) or centiseconds after a
+// dot (ASS)
+fn parse_timestamp(timestamp: &str) -> Option<f64> {
+    let mut parts = timestamp.trim().split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.replace(',', ".").parse().ok()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+// Numbered blocks of a "start --> end" line followed by the text, separated
+// by blank lines
+fn parse_srt(contents: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut lines = contents.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((start, end)) = line.split_once("-->") else {
+            continue;
+        };
+
+        // Some files put the position of the cue after the end time
+        let end = end.split_whitespace().next().unwrap_or_default();
+
+        let (Some(start), Some(end)) = (parse_timestamp(start), parse_timestamp(end)) else {
+            continue;
+        };
+
+        let text: Vec<String> = lines
+            .by_ref()
+            .take_while(|line| !line.trim().is_empty())
+            .map(|line| strip_tags(line, '<', '>'))
+            .collect();
+
+        cues.push(Cue {
+            start,
+            end,
+            text: text.join("\n"),
+        });
+    }
+
+    cues
+}
+
+// Dialogue lines of the [Events] section, laid out by its Format line
+fn parse_ass(contents: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut format: Vec<String> = Vec::new();
+    let mut in_events = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[Events]");
+            continue;
+        }
+
+        if !in_events {
+            continue;
+        }
+
+        if let Some(fields) = line.strip_prefix("Format:") {
+            format = fields
+                .split(',')
+                .map(|field| field.trim().to_lowercase())
+                .collect();
+            continue;
+        }
+
+        let Some(values) = line.strip_prefix("Dialogue:") else {
+            continue;
+        };
+
+        // Text comes last and can have commas of its own
+        let values: Vec<&str> = values.splitn(format.len().max(1), ',').collect();
+        let field = |name: &str| {
+            format
+                .iter()
+                .position(|field| field == name)
+                .and_then(|i| values.get(i))
+        };
+
+        let (Some(start), Some(end), Some(text)) = (
+            field("start").and_then(|start| parse_timestamp(start)),
+            field("end").and_then(|end| parse_timestamp(end)),
+            field("text"),
+        ) else {
+            continue;
+        };
+
+        let text = strip_tags(text, '{', '}')
+            .replace("\\N", "\n")
+            .replace("\\n", "\n")
+            .replace("\\h", " ");
+
+        cues.push(Cue {
+            start,
+            end,
+            text: text.trim().to_string(),
+        });
+    }
+
+    cues
+}
+
+// Drop styling like <i> in SRT or {\an8} in ASS, which would show up as text
+fn strip_tags(text: &str, open: char, close: char) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut in_tag = false;
+
+    for c in text.chars() {
+        match c {
+            c if c == open => in_tag = true,
+            c if c == close && in_tag => in_tag = false,
+            c if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+
+    stripped
+}
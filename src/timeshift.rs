@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use ndarray::Array3;
+
+use crate::utils::ffprobe::DurationType;
+use crate::video::DecodedFrame;
+
+// Seconds of frames kept in memory before the rest goes to disk
+const MEMORY_SECS: u64 = 10;
+
+// Seconds of frames per spill file, a file is deleted once it's been read
+// through so the disk only holds about what's still buffered
+const SEGMENT_SECS: u64 = 30;
+
+// Frames that arrived from a live stream but haven't been shown yet, e.g.
+// while paused. The oldest are kept in memory and the newer ones spill into
+// zstd compressed temporary files, up to `max_frames` in total after which the
+// oldest are dropped
+pub struct Timeshift {
+    memory: VecDeque<DecodedFrame>,
+    memory_frames: usize,
+    // Frames newer than everything in memory, in arrival order
+    spill: Option<Spill>,
+    max_frames: usize,
+    fps: u64,
+}
+
+// Spilled frames across segment files, oldest first
+struct Spill {
+    segments: VecDeque<Segment>,
+    segment_frames: usize,
+    next_segment: u64,
+}
+
+struct Segment {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    reader: BufReader<File>,
+    // Frames written but not read yet
+    frames: usize,
+    written: usize,
+}
+
+impl Timeshift {
+    pub fn new(fps: u64, max_secs: u64) -> Self {
+        Self {
+            memory: VecDeque::new(),
+            memory_frames: (fps * MEMORY_SECS) as usize,
+            spill: None,
+            max_frames: (fps * max_secs).max(1) as usize,
+            fps: fps.max(1),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.memory.len() + self.spill.as_ref().map_or(0, Spill::frames)
+    }
+
+    // How far playback is behind the live edge in seconds
+    pub fn behind(&self) -> f32 {
+        self.len() as f32 / self.fps as f32
+    }
+
+    pub fn push(&mut self, frame: DecodedFrame) {
+        if self.len() >= self.max_frames {
+            self.pop();
+        }
+
+        // Once anything is on disk, everything newer has to follow it there
+        if self.spill.is_none() && self.memory.len() < self.memory_frames {
+            self.memory.push_back(frame);
+            return;
+        }
+
+        if self.spill.is_none() {
+            self.spill = Some(Spill::new((self.fps * SEGMENT_SECS) as usize));
+        }
+
+        let written = self
+            .spill
+            .as_mut()
+            .is_some_and(|spill| spill.write(&frame).is_ok());
+
+        if written {
+            return;
+        }
+
+        // Frames still on disk play before anything newer, so this one can't
+        // jump ahead of them in memory and is dropped
+        if self.spill.as_ref().is_some_and(|spill| spill.frames() > 0) {
+            return;
+        }
+
+        // Without a working disk the oldest frames in memory make way instead
+        self.spill = None;
+
+        if self.memory.len() >= self.memory_frames {
+            self.memory.pop_front();
+        }
+
+        self.memory.push_back(frame);
+    }
+
+    pub fn pop(&mut self) -> Option<DecodedFrame> {
+        if let Some(frame) = self.memory.pop_front() {
+            return Some(frame);
+        }
+
+        let spill = self.spill.as_mut()?;
+        let frame = spill.read().ok();
+
+        // All caught up with the disk, go back to memory
+        if frame.is_none() || spill.frames() == 0 {
+            self.spill = None;
+        }
+
+        frame
+    }
+
+    // Jump to the live edge
+    pub fn clear(&mut self) {
+        self.memory.clear();
+        self.spill = None;
+    }
+}
+
+impl Spill {
+    fn new(segment_frames: usize) -> Self {
+        Self {
+            segments: VecDeque::new(),
+            segment_frames: segment_frames.max(1),
+            next_segment: 0,
+        }
+    }
+
+    fn frames(&self) -> usize {
+        self.segments.iter().map(|segment| segment.frames).sum()
+    }
+
+    fn write(&mut self, frame: &DecodedFrame) -> io::Result<()> {
+        let full = self
+            .segments
+            .back()
+            .is_none_or(|segment| segment.written >= self.segment_frames);
+
+        if full {
+            self.segments.push_back(Segment::create(self.next_segment)?);
+            self.next_segment += 1;
+        }
+
+        match self.segments.back_mut() {
+            Some(segment) => segment.write(frame),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn read(&mut self) -> io::Result<DecodedFrame> {
+        let segment = self
+            .segments
+            .front_mut()
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        let frame = segment.read();
+
+        // Read through a segment nothing more goes into, its file can go
+        if segment.frames == 0 && segment.written >= self.segment_frames {
+            self.segments.pop_front();
+        }
+
+        frame
+    }
+}
+
+impl Segment {
+    fn create(number: u64) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "window-timeshift-{}-{}",
+            std::process::id(),
+            number
+        ));
+        let writer = BufWriter::new(File::create(&path)?);
+        let reader = BufReader::new(File::open(&path)?);
+
+        // Unix keeps the file around while it's open, so it's gone however
+        // playback exits. Elsewhere dropping the segment removes it
+        let _ = fs::remove_file(&path);
+
+        Ok(Self {
+            path,
+            writer,
+            reader,
+            frames: 0,
+            written: 0,
+        })
+    }
+
+    // Each frame is its height, width and channels as u32s, its timestamp as
+    // an f64 and the length of the compressed pixels as a u32, all little
+    // endian, followed by the compressed pixels
+    fn write(&mut self, (frame, _, pts): &DecodedFrame) -> io::Result<()> {
+        let (height, width, channels) = frame.dim();
+        let pixels = zstd::bulk::compress(frame.as_slice().unwrap_or_default(), 1)?;
+
+        for value in [height, width, channels] {
+            self.writer.write_all(&(value as u32).to_le_bytes())?;
+        }
+
+        self.writer.write_all(&pts.to_le_bytes())?;
+        self.writer
+            .write_all(&(pixels.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&pixels)?;
+
+        self.frames += 1;
+        self.written += 1;
+
+        Ok(())
+    }
+
+    fn read(&mut self) -> io::Result<DecodedFrame> {
+        self.writer.flush()?;
+
+        let mut u32_bytes = [0u8; 4];
+        let mut read_u32 = |reader: &mut BufReader<File>| {
+            reader
+                .read_exact(&mut u32_bytes)
+                .map(|_| u32::from_le_bytes(u32_bytes) as usize)
+        };
+
+        let height = read_u32(&mut self.reader)?;
+        let width = read_u32(&mut self.reader)?;
+        let channels = read_u32(&mut self.reader)?;
+
+        let mut pts_bytes = [0u8; 8];
+        self.reader.read_exact(&mut pts_bytes)?;
+
+        let length = read_u32(&mut self.reader)?;
+        let mut compressed = vec![0u8; length];
+        self.reader.read_exact(&mut compressed)?;
+
+        self.frames -= 1;
+
+        let pixels = zstd::bulk::decompress(&compressed, height * width * channels)?;
+        let frame = Array3::from_shape_vec((height, width, channels), pixels)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok((frame, DurationType::Live, f64::from_le_bytes(pts_bytes)))
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
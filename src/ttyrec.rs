@@ -0,0 +1,71 @@
+use anyhow::Context;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+enum Output {
+    Plain(BufWriter<File>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+// Records the rendered output in ttyrec format for ttyplay or ipbt, one
+// record per flushed frame. Paths ending in .zst are zstd compressed
+pub struct Ttyrec {
+    output: Option<Output>,
+}
+
+impl Ttyrec {
+    pub fn create(path: &str) -> anyhow::Result<Self> {
+        let file = BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create ttyrec {}", path))?,
+        );
+
+        let output = if path.ends_with(".zst") {
+            Output::Zstd(zstd::Encoder::new(file, 0)?)
+        } else {
+            Output::Plain(file)
+        };
+
+        let mut ttyrec = Self {
+            output: Some(output),
+        };
+
+        // Start from a blank screen with the cursor hidden, like playback does
+        ttyrec.record(b"\x1b[2J\x1b[?25l");
+
+        Ok(ttyrec)
+    }
+
+    // Each record is the time it was written then the length of the data,
+    // all little endian u32s, followed by the data itself
+    pub fn record(&mut self, data: &[u8]) {
+        let writer: &mut dyn Write = match &mut self.output {
+            Some(Output::Plain(writer)) => writer,
+            Some(Output::Zstd(writer)) => writer,
+            None => return,
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let mut header = [0u8; 12];
+        header[0..4].copy_from_slice(&(now.as_secs() as u32).to_le_bytes());
+        header[4..8].copy_from_slice(&now.subsec_micros().to_le_bytes());
+        header[8..12].copy_from_slice(&(data.len() as u32).to_le_bytes());
+
+        // A recording failing shouldn't stop playback
+        let _ = writer
+            .write_all(&header)
+            .and_then(|_| writer.write_all(data));
+    }
+
+    // Flush what's buffered, and end the zstd frame so the file decompresses
+    pub fn finish(&mut self) {
+        let _ = match self.output.take() {
+            Some(Output::Plain(mut writer)) => writer.flush(),
+            Some(Output::Zstd(encoder)) => encoder.finish().and_then(|mut writer| writer.flush()),
+            None => Ok(()),
+        };
+    }
+}
@@ -0,0 +1,32 @@
+use anyhow::Context;
+use std::process::{Command, Stdio};
+use video_rs::{Location, Url};
+
+// Runs the user's refresh command for a freshly signed URL when a token URL
+// has expired mid-playback. The first line it prints is the new URL
+pub fn refresh_url(command: &str) -> anyhow::Result<Location> {
+    let mut args = command.split_whitespace();
+    let program = args.next().context("Empty URL refresh command")?;
+
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    if !output.status.success() {
+        anyhow::bail!("{} exited with {}", program, output.status);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let url = stdout
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .with_context(|| format!("{} printed no URL", program))?;
+
+    Ok(Location::Network(url.parse::<Url>().with_context(
+        || format!("{} printed an invalid URL: {}", program, url),
+    )?))
+}
@@ -1,18 +1,34 @@
+use chrono::NaiveTime;
 use clap::Parser;
+use crossterm::style::Color;
+use std::time::Duration;
+use unicode_width::UnicodeWidthChar;
+
+use crate::alerts::Alert;
+use crate::effects::Effect;
 use serde::Serialize;
 use video_rs::hwaccel::HardwareAccelerationDeviceType;
 
 #[derive(clap::ValueEnum, Clone, Default, Debug, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum CharacterMode {
+    // Picked from the terminal's cell size, see `auto_character_mode`
     #[default]
+    Auto,
     Block,
+    // Two pixels stacked in each cell, the top one colored by ▀ and the bottom
+    // one by the background
+    HalfBlock,
+    // 2x4 dots per cell
+    Braille,
     Dots,
     Ascii,
     AsciiExtended,
     AsciiWindows,
     Numbers,
     Blocks,
+    Emoji,
+    Sextant,
 }
 
 #[derive(clap::ValueEnum, Clone, Default, Debug, Serialize)]
@@ -21,6 +37,63 @@ pub enum ScaleMode {
     #[default]
     Fit,
     Stretch,
+    /// Fill the whole area, cropping the edges that don't fit
+    Cover,
+}
+
+#[derive(clap::ValueEnum, Clone, Default, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorDepth {
+    /// 24-bit RGB
+    #[default]
+    Truecolor,
+    /// xterm 256 color palette
+    #[value(name = "256")]
+    #[serde(rename = "256")]
+    Ansi256,
+    /// Standard 16 ANSI colors
+    #[value(name = "16")]
+    #[serde(rename = "16")]
+    Ansi16,
+    /// The 8 basic ANSI colors, without the bright ones
+    #[value(name = "8")]
+    #[serde(rename = "8")]
+    Ansi8,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorBlindFilter {
+    /// Simulate red blindness
+    Protanopia,
+    /// Simulate green blindness
+    Deuteranopia,
+    /// Simulate blue blindness
+    Tritanopia,
+    /// Shift colors so red-green color blind viewers can tell them apart
+    Daltonize,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Green phosphor monochrome monitor
+    Phosphor,
+    /// Amber CRT monitor
+    Amber,
+    /// Green tinted katakana rain
+    Matrix,
+}
+
+#[derive(clap::ValueEnum, Clone, Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnUnfocus {
+    /// Stop drawing until focused again
+    Pause,
+    /// Keep drawing at a couple of frames per second
+    Throttle,
+    /// Keep drawing as usual
+    Ignore,
 }
 
 // Hardware acceleration device type but clap compatible
@@ -74,8 +147,138 @@ impl HardwareAcceleration {
     }
 }
 
+// Name a value is given by on the command line
+pub fn value_name(value: &impl clap::ValueEnum) -> String {
+    value
+        .to_possible_value()
+        .map_or(String::new(), |value| value.get_name().to_string())
+}
+
+// Every ramp character has to fill exactly one column or the cell layout shifts
+fn parse_ramp(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err("ramp cannot be empty".to_string());
+    }
+
+    match s.chars().find(|c| c.width() != Some(1)) {
+        Some(c) => Err(format!(
+            "{:?} (U+{:04X}) is not a single column wide character",
+            c, c as u32
+        )),
+        None => Ok(s.to_string()),
+    }
+}
+
+// Either a "#rrggbb" hex color or a named color like "dark_grey"
+fn parse_color(s: &str) -> Result<Color, String> {
+    if let Some(hex) = s.strip_prefix('#') {
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+
+        return match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb { r, g, b }),
+            _ => Err(format!("invalid hex color: {}", s)),
+        };
+    }
+
+    Color::try_from(s).map_err(|_| format!("unknown color: {} (expected #rrggbb or a name)", s))
+}
+
+// Weight of previous frames in temporal smoothing, 1 would freeze the picture
+fn parse_smoothing(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(strength) if (0.0..1.0).contains(&strength) => Ok(strength),
+        _ => Err(format!(
+            "invalid smoothing: {} (expected 0 up to 1, e.g. 0.5)",
+            s
+        )),
+    }
+}
+
+// Gamma correction, above 1 brightens the midtones
+fn parse_gamma(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(gamma) if gamma > 0.0 && gamma.is_finite() => Ok(gamma),
+        _ => Err(format!("invalid gamma: {} (expected above 0, e.g. 1.2)", s)),
+    }
+}
+
+// Character cell aspect ratio as "w:h", e.g. "1:2"
+fn parse_cell_aspect(s: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid cell aspect: {} (expected w:h, e.g. 1:2)", s);
+    let (width, height) = s.split_once(':').ok_or_else(invalid)?;
+
+    match (width.parse::<u32>(), height.parse::<u32>()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(invalid()),
+    }
+}
+
+// Local wall clock time as "HH:MM" or "HH:MM:SS"
+fn parse_clock_time(s: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M:%S"))
+        .map_err(|_| format!("invalid time: {} (expected HH:MM)", s))
+}
+
+// A point in the video as "HH:MM:SS", "MM:SS" or seconds, e.g. "1:30" or "90.5"
+fn parse_timestamp(s: &str) -> Result<f32, String> {
+    let invalid = || format!("invalid time: {} (expected HH:MM:SS, MM:SS or seconds)", s);
+    let parts: Vec<&str> = s.split(':').collect();
+
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    let mut seconds = 0.0;
+
+    for part in parts {
+        match part.parse::<f32>() {
+            Ok(value) if value >= 0.0 && value.is_finite() => seconds = seconds * 60.0 + value,
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(seconds)
+}
+
+// Durations like "90s", "45m", "2h" or "1h30m"
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let mut total = 0;
+    let mut number = String::new();
+
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("invalid duration: {} (expected e.g. 1h30m)", s)),
+        };
+
+        let value: u64 = number
+            .parse()
+            .map_err(|_| format!("invalid duration: {} (expected e.g. 1h30m)", s))?;
+
+        total += value * unit;
+        number.clear();
+    }
+
+    if !number.is_empty() || total == 0 {
+        return Err(format!("invalid duration: {} (expected e.g. 1h30m)", s));
+    }
+
+    Ok(Duration::from_secs(total))
+}
+
 #[derive(Parser, Debug)]
-#[command(version, author, about, long_about = None)]
+#[command(version, author, about, long_about = None, args_override_self = true)]
 pub struct Args {
     /// The video file location, url, or youtube link
     pub input: String,
@@ -86,13 +289,31 @@ pub struct Args {
     pub pixel_clear_distance: Option<u16>,
 
     /// The character mode to use
-    #[clap(short, long, default_value = "block")]
+    #[clap(short, long, default_value = "auto")]
     pub mode: Option<CharacterMode>,
 
+    /// Custom character ramp ordered from dark to bright, overriding the mode's characters
+    #[clap(long, value_parser = parse_ramp)]
+    pub ramp: Option<String>,
+
+    /// Shorthand for --mode emoji, mapping colors to emoji squares two columns wide
+    #[clap(long, action)]
+    pub emoji: bool,
+
     /// The scale mode to use
     #[clap(short, long, default_value = "fit")]
     pub scale: Option<ScaleMode>,
 
+    /// Color of the bars around the video when it doesn't fill the terminal
+    /// e.g. "#202020" or "dark_grey"
+    #[clap(long, value_parser = parse_color)]
+    pub letterbox_color: Option<Color>,
+
+    /// Width to height of a character cell, e.g. "1:1" for square-cell fonts,
+    /// overriding the one detected from the terminal
+    #[clap(long, value_name = "W:H", value_parser = parse_cell_aspect)]
+    pub cell_aspect: Option<(u32, u32)>,
+
     /// Allow the framerate to exceed the video's framerate
     #[clap(short, long, action)]
     pub remove_fps_cap: bool,
@@ -105,7 +326,288 @@ pub struct Args {
     #[clap(long, short, action)]
     pub fullscreen: bool,
 
+    /// Draw nothing but the video, without header, footer or overlays, and send
+    /// warnings to stderr. For piping the output into recorders
+    #[clap(long, visible_alias = "quiet", action)]
+    pub no_ui: bool,
+
     /// Render without color
     #[clap(long, short, action)]
     pub no_color: bool,
+
+    /// Colors the terminal can display, detected from COLORTERM and TERM by default
+    /// Setting this also overrides NO_COLOR
+    #[clap(long)]
+    pub color_depth: Option<ColorDepth>,
+
+    /// Gamma correction for terminals that show midtones too dark (above 1) or
+    /// too bright (below 1), see window calibrate
+    #[clap(long, default_value = "1", value_parser = parse_gamma)]
+    pub gamma: f32,
+
+    /// Color blindness simulation or assist filter to apply
+    #[clap(long)]
+    pub cb_filter: Option<ColorBlindFilter>,
+
+    /// Effect to apply to each frame, can be repeated to chain effects
+    /// [possible values: invert, posterize[=LEVELS], sepia, grayscale, trail[=FRAMES]]
+    #[clap(long = "effect")]
+    pub effects: Vec<Effect>,
+
+    /// Stylized palette and character preset to render with
+    #[clap(long)]
+    pub theme: Option<Theme>,
+
+    /// Draw over the current screen instead of switching to the alternate screen
+    #[clap(long, action)]
+    pub no_alt_screen: bool,
+
+    /// Command to play the audio with, {url} and {start} (seconds) are substituted
+    /// e.g. "mpv --no-video --start={start} {url}". {ipc} is a socket path for
+    /// mpv's --input-ipc-server, which keeps the audio in step with the video.
+    /// Defaults to mpv or ffplay when installed
+    #[clap(long)]
+    pub audio_cmd: Option<String>,
+
+    /// Play the video without sound
+    #[clap(long, action, conflicts_with = "audio_cmd")]
+    pub no_audio: bool,
+
+    /// Play the audio from another input than the video, e.g. a radio stream over
+    /// muted scenery. Only loosely synchronized, seeking leaves it playing
+    #[clap(long, conflicts_with = "no_audio")]
+    pub audio_from: Option<String>,
+
+    /// Command transcribing the audio into live captions shown over the video, one
+    /// per line on its stdout, e.g. a whisper.cpp script. {url} and {start} are
+    /// substituted like in --audio-cmd
+    #[clap(long)]
+    pub captions_cmd: Option<String>,
+
+    /// Subtitle file to show over the video, SRT or ASS. Takes the place of
+    /// --captions-cmd captions while a subtitle is showing
+    #[clap(long)]
+    pub sub: Option<String>,
+
+    /// Extra HTTP header sent with every request for the input, including the
+    /// keys of encrypted HLS streams, e.g. "Authorization: Bearer ...". Can be repeated
+    #[clap(long = "http-header", value_name = "HEADER")]
+    pub http_headers: Vec<String>,
+
+    /// Cookies sent with every request for the input, as Set-Cookie style lines
+    /// e.g. "session=abc; path=/; domain=example.com"
+    #[clap(long)]
+    pub cookies: Option<String>,
+
+    /// Command printing a freshly signed URL for the input, run when reading a
+    /// token URL fails so an expired link doesn't end playback
+    #[clap(long)]
+    pub url_refresh_cmd: Option<String>,
+
+    /// Resolve YouTube links with yt-dlp every time instead of reusing metadata
+    /// cached by video ID for a few minutes
+    #[clap(long, action)]
+    pub no_cache: bool,
+
+    /// Where to cache things like resolved YouTube links [default: the platform's
+    /// cache directory, e.g. ~/.cache/window]
+    #[clap(long)]
+    pub cache_dir: Option<String>,
+
+    /// Config file with default flags, one per line as typed on the command line
+    /// [default: the platform's config directory, e.g. ~/.config/window/config]
+    #[clap(long)]
+    pub config: Option<String>,
+
+    /// Named pipe to read commands from, one per line: "seek <seconds>", "pause",
+    /// "quit" or "quality <+|-|1-6>"
+    #[clap(long)]
+    pub input_fifo: Option<String>,
+
+    /// Serve playback metrics for Prometheus to scrape on this address, like
+    /// 127.0.0.1:9090
+    #[clap(long)]
+    pub metrics: Option<String>,
+
+    /// Overlay the source timestamp of each frame as HH:MM:SS:FF
+    #[clap(long, action)]
+    pub timecode: bool,
+
+    /// Save battery: render at most 15 fps, skip small pixel changes and stop
+    /// drawing while the terminal is unfocused
+    #[clap(long, action)]
+    pub power_save: bool,
+
+    /// Profile for slow devices like a Raspberry Pi over SSH or serial: quadrant
+    /// blocks instead of sextants so less is decoded, and at most 256 colors
+    #[clap(long, action)]
+    pub low_end: bool,
+
+    /// Render for a serial console at this baud rate: a fixed 80x24 ASCII layout
+    /// in 8 colors, with the framerate and bytes per frame the line can carry
+    #[clap(long, value_name = "BAUD")]
+    pub serial: Option<u32>,
+
+    /// What to do while the terminal is unfocused, audio keeps playing either way
+    /// [default: pause with --power-save, ignore otherwise]
+    #[clap(long)]
+    pub on_unfocus: Option<OnUnfocus>,
+
+    /// Redraw at most this many cells per frame for slow links, moving regions
+    /// first while static background catches up over the following frames
+    #[clap(long)]
+    pub cell_budget: Option<usize>,
+
+    /// Write at most this many bytes for the cells of a frame, the rest are
+    /// redrawn over the following frames. Shown in the footer when set
+    #[clap(long)]
+    pub max_frame_bytes: Option<usize>,
+
+    /// Blend each frame with the ones before it to calm flickering on grainy
+    /// sources, from 0 (off) up to 1, e.g. 0.5 averages about the last 3 frames
+    #[clap(long, value_name = "STRENGTH", value_parser = parse_smoothing)]
+    pub smoothing: Option<f32>,
+
+    /// Wait until this local time (HH:MM) before starting playback
+    #[clap(long, value_parser = parse_clock_time)]
+    pub at: Option<NaiveTime>,
+
+    /// Stop playback after this long, e.g. "90m" or "2h"
+    #[clap(long = "for", value_parser = parse_duration)]
+    pub play_for: Option<Duration>,
+
+    /// Keep checking an offline channel or upcoming premiere this often, e.g. "30s",
+    /// and start playing once it goes live
+    #[clap(long, value_parser = parse_duration)]
+    pub wait_for_stream: Option<Duration>,
+
+    /// Unix time in seconds at which the video started for everyone, playback
+    /// follows the system clock from there to stay in step with other instances
+    #[clap(long, value_name = "UNIX_TIME")]
+    pub sync_epoch: Option<f64>,
+
+    /// Longest a paused live stream is buffered for, e.g. "30m", the oldest frames
+    /// are dropped past that. Anything over a few seconds is kept on disk
+    #[clap(long, value_parser = parse_duration, default_value = "10m")]
+    pub timeshift_max: Duration,
+
+    /// Run CMD every INTERVAL (e.g. "5s") with the playback state as JSON on stdin
+    #[clap(long, num_args = 2, value_names = ["INTERVAL", "CMD"])]
+    pub on_frame_exec_every: Option<Vec<String>>,
+
+    /// Run a command on playback events (start, pause, resume, seek, end)
+    /// with the playback state as JSON on stdin
+    #[clap(long)]
+    pub on_event_exec: Option<String>,
+
+    /// Ring the terminal bell or flash the screen on a playback event (end, error or
+    /// online), e.g. "end=bell" or "online=flash". Can be repeated
+    #[clap(long = "alert")]
+    pub alerts: Vec<Alert>,
+
+    /// Keep a JSON file with the title, artist, position and artwork of what's
+    /// playing up to date, for status bars to poll
+    #[clap(long)]
+    pub now_playing_file: Option<String>,
+
+    /// Start playing this far into the video, as HH:MM:SS, MM:SS or seconds
+    #[clap(long, value_name = "TIME", value_parser = parse_timestamp)]
+    pub start: Option<f32>,
+
+    /// Stop playing at this point in the video, as HH:MM:SS, MM:SS or seconds.
+    /// Progress is shown for just the part between --start and --end
+    #[clap(long, value_name = "TIME", value_parser = parse_timestamp)]
+    pub end: Option<f32>,
+
+    /// Look for the black cut that ends an intro and offer to skip to it with s
+    #[clap(long, action)]
+    pub skip_intro: bool,
+
+    /// Start over from the beginning when the video ends instead of showing the
+    /// end screen
+    #[clap(long = "loop", action)]
+    pub loop_playback: bool,
+
+    /// Play the video this many times in all, then end as usual. Implies --loop
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub loop_count: Option<u32>,
+
+    /// Signage mode: fullscreen, quit keys disabled, loops forever and keeps
+    /// reconnecting to broken streams. Stop it with a signal or the input fifo
+    #[clap(long, action)]
+    pub kiosk: bool,
+
+    /// Save the video stream to a file while playing, remuxed without re-encoding
+    #[clap(long)]
+    pub record_source: Option<String>,
+
+    /// Save the rendered output in ttyrec format for ttyplay or ipbt,
+    /// zstd compressed when the path ends in .zst
+    #[clap(long)]
+    pub record_ttyrec: Option<String>,
+
+    /// Resolve the input and print what would be played as JSON (stream URL, format,
+    /// codec, fps, duration, renderer and size) without playing it
+    #[clap(long, action)]
+    pub dry_run: bool,
+
+    /// Print a performance report with a frame time histogram on exit
+    #[clap(long, action)]
+    pub report: bool,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "window modes",
+    about = "Show a frame in every character mode side by side, to pick the one that suits the terminal and font best"
+)]
+pub struct ModesArgs {
+    /// The video or image to take the frame from
+    pub input: String,
+
+    /// Seconds into the video to take the frame from
+    #[clap(long, default_value = "0")]
+    pub at: f32,
+
+    /// Colors to start with, c switches between them [default: detected from
+    /// COLORTERM and TERM]
+    #[clap(long)]
+    pub color_depth: Option<ColorDepth>,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "window compare",
+    about = "Play two videos side by side on the same timeline, e.g. to compare encodes"
+)]
+pub struct CompareArgs {
+    /// The video shown on the left
+    pub a: String,
+
+    /// The video shown on the right
+    pub b: String,
+
+    /// Character mode for both [default: auto]
+    #[clap(short, long)]
+    pub mode: Option<CharacterMode>,
+
+    /// Colors for both [default: detected from COLORTERM and TERM]
+    #[clap(long)]
+    pub color_depth: Option<ColorDepth>,
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    bin_name = "window calibrate",
+    about = "Show test patterns to tune gamma and cell aspect for this terminal, saving them into the config"
+)]
+pub struct CalibrateArgs {
+    /// The character mode to calibrate with [default: the one in the config]
+    #[clap(short, long)]
+    pub mode: Option<CharacterMode>,
+
+    /// Config file to save into [default: the platform's config directory, e.g.
+    /// ~/.config/window/config]
+    #[clap(long)]
+    pub config: Option<String>,
 }
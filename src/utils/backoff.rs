@@ -0,0 +1,17 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const BASE_DELAY_MS: u64 = 250;
+const MAX_DELAY_MS: u64 = 8000;
+
+// Exponential backoff for the given retry attempt (starting at 1), with up to
+// half the delay added as jitter so retries don't line up with the server's hiccups
+pub fn backoff_delay(attempt: u32) -> Duration {
+    let delay = (BASE_DELAY_MS << attempt.saturating_sub(1).min(16)).min(MAX_DELAY_MS);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.subsec_nanos() as u64)
+        .unwrap_or(0);
+
+    Duration::from_millis(delay + nanos % (delay / 2 + 1))
+}
@@ -0,0 +1,48 @@
+use super::get_grey::get_grey;
+
+type Rgb = (u8, u8, u8);
+
+// Braille dot bit for each pixel of a 2x4 cell, left to right then top to
+// bottom. The bottom row was added to braille later, hence the odd order
+const DOT_BITS: [u8; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+
+// Braille pattern for a 2x4 mask, bit 0 being the top left and bit 7 the bottom right
+pub fn braille_char(mask: u8) -> char {
+    let dots = DOT_BITS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .fold(0u32, |dots, (_, &bit)| dots | bit as u32);
+
+    char::from_u32(0x2800 + dots).unwrap()
+}
+
+// Light up the pixels at least as bright as the cell's average, drawn in the
+// cell's average color on black. The color carries the brightness, the dots
+// only the shape
+pub fn braille_fit(pixels: &[Rgb]) -> (u8, Rgb, Rgb) {
+    let count = pixels.len().max(1) as u32;
+    let mut sum = [0u32; 3];
+    let mut grey_sum = 0u32;
+
+    for &(r, g, b) in pixels {
+        sum[0] += r as u32;
+        sum[1] += g as u32;
+        sum[2] += b as u32;
+        grey_sum += get_grey(r, g, b) as u32;
+    }
+
+    let mask = pixels
+        .iter()
+        .enumerate()
+        .filter(|(_, &(r, g, b))| get_grey(r, g, b) as u32 * count >= grey_sum)
+        .fold(0u8, |mask, (i, _)| mask | (1 << i));
+
+    let average = (
+        (sum[0] / count) as u8,
+        (sum[1] / count) as u8,
+        (sum[2] / count) as u8,
+    );
+
+    (mask, average, (0, 0, 0))
+}
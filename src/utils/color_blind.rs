@@ -0,0 +1,43 @@
+use crate::utils::args::ColorBlindFilter;
+
+type Matrix = [[f32; 3]; 3];
+
+// Simulation matrices for dichromatic vision, applied to sRGB values
+const PROTANOPIA: Matrix = [
+    [0.567, 0.433, 0.0],
+    [0.558, 0.442, 0.0],
+    [0.0, 0.242, 0.758],
+];
+const DEUTERANOPIA: Matrix = [[0.625, 0.375, 0.0], [0.7, 0.3, 0.0], [0.0, 0.3, 0.7]];
+const TRITANOPIA: Matrix = [[0.95, 0.05, 0.0], [0.0, 0.433, 0.567], [0.0, 0.475, 0.525]];
+
+fn multiply(m: &Matrix, (r, g, b): (f32, f32, f32)) -> (f32, f32, f32) {
+    (
+        m[0][0].mul_add(r, m[0][1].mul_add(g, m[0][2] * b)),
+        m[1][0].mul_add(r, m[1][1].mul_add(g, m[1][2] * b)),
+        m[2][0].mul_add(r, m[2][1].mul_add(g, m[2][2] * b)),
+    )
+}
+
+pub fn color_blind_filter(filter: &ColorBlindFilter, (r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+    let rgb = (r as f32, g as f32, b as f32);
+
+    let (r, g, b) = match filter {
+        ColorBlindFilter::Protanopia => multiply(&PROTANOPIA, rgb),
+        ColorBlindFilter::Deuteranopia => multiply(&DEUTERANOPIA, rgb),
+        ColorBlindFilter::Tritanopia => multiply(&TRITANOPIA, rgb),
+        // Shift the information lost to a red-green deficiency into channels still visible
+        ColorBlindFilter::Daltonize => {
+            let (sr, sg, sb) = multiply(&DEUTERANOPIA, rgb);
+            let (er, eg, eb) = (rgb.0 - sr, rgb.1 - sg, rgb.2 - sb);
+
+            (rgb.0, rgb.1 + 0.7 * er + eg, rgb.2 + 0.7 * er + eb)
+        }
+    };
+
+    (
+        r.clamp(0.0, 255.0) as u8,
+        g.clamp(0.0, 255.0) as u8,
+        b.clamp(0.0, 255.0) as u8,
+    )
+}
@@ -0,0 +1,20 @@
+use std::fs;
+use std::time::Duration;
+
+// procfs reports times in clock ticks, USER_HZ is 100 on all mainstream kernels
+const CLOCK_TICKS_PER_SEC: u64 = 100;
+
+// CPU time used by the process so far, only available on Linux through procfs
+pub fn process_cpu_time() -> Option<Duration> {
+    let stat = fs::read_to_string("/proc/self/stat").ok()?;
+
+    // The command name can contain spaces, so count fields from after it,
+    // utime and stime being the 14th and 15th fields
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+
+    Some(Duration::from_millis(
+        (utime + stime) * 1000 / CLOCK_TICKS_PER_SEC,
+    ))
+}
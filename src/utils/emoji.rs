@@ -0,0 +1,22 @@
+use crate::utils::rgb_distance::rgb_distance;
+
+// Colored square emoji and the approximate color they render in
+const EMOJI_SQUARES: [(char, (u8, u8, u8)); 9] = [
+    ('\u{1F7E5}', (221, 46, 68)),
+    ('\u{1F7E7}', (244, 144, 12)),
+    ('\u{1F7E8}', (253, 203, 88)),
+    ('\u{1F7E9}', (120, 177, 89)),
+    ('\u{1F7E6}', (85, 172, 238)),
+    ('\u{1F7EA}', (170, 142, 214)),
+    ('\u{1F7EB}', (193, 105, 79)),
+    ('\u{2B1B}', (49, 55, 61)),
+    ('\u{2B1C}', (230, 231, 232)),
+];
+
+pub fn nearest_emoji(rgb: (u8, u8, u8)) -> char {
+    EMOJI_SQUARES
+        .iter()
+        .min_by(|(_, a), (_, b)| rgb_distance(rgb, *a).total_cmp(&rgb_distance(rgb, *b)))
+        .map(|(emoji, _)| *emoji)
+        .unwrap()
+}
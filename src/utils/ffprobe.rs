@@ -1,5 +1,6 @@
 use anyhow::Result;
-use std::ffi::CString;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
 use std::ptr;
 use video_rs::ffmpeg::ffi::*;
 
@@ -11,82 +12,98 @@ pub fn ffmpeg_initialize() -> Result<()> {
     Ok(())
 }
 
-pub fn ffprobe_get_fps(url: &str) -> Result<u64> {
-    ffmpeg_initialize()?;
-
-    let c_url = CString::new(url)?;
-    let mut format_context = ptr::null_mut();
-
-    unsafe {
-        if avformat_open_input(
-            &mut format_context,
-            c_url.as_ptr(),
-            ptr::null_mut(),
-            ptr::null_mut(),
-        ) != 0
-        {
-            return Err(anyhow::anyhow!("Failed to open input"));
-        }
-
-        if avformat_find_stream_info(format_context, ptr::null_mut()) < 0 {
-            return Err(anyhow::anyhow!("Failed to find stream info"));
-        }
-
-        let stream = (*format_context).streams;
-        let video_stream_index = (0..(*format_context).nb_streams)
-            .find(|&i| {
-                let codec_parameters = (*(*stream.add(i as usize))).codecpar;
-                (*codec_parameters).codec_type == AVMediaType::AVMEDIA_TYPE_VIDEO
-            })
-            .ok_or_else(|| anyhow::anyhow!("No video stream found"))?;
-
-        let codec_parameters = (*(*stream.add(video_stream_index as usize))).codecpar;
-        let frame_rate = (*codec_parameters).framerate;
-
-        if frame_rate.den != 0 {
-            Ok(frame_rate.num as u64 / frame_rate.den as u64)
-        } else {
-            Err(anyhow::anyhow!("Invalid frame rate"))
-        }
-    }
-}
-
 #[derive(Debug, Clone, Copy)]
 pub enum DurationType {
     Fixed(u64),
     Live,
 }
 
-pub async fn ffprobe_get_duration(url: &str) -> Result<DurationType> {
+// Everything playback needs to know about an input, from a single open
+pub struct Probe {
+    // None when the stream doesn't declare a frame rate
+    pub fps: Option<u64>,
+    pub duration: DurationType,
+    pub size: (u32, u32),
+    // Name of the video codec, and whether this ffmpeg has a decoder for it
+    pub codec: String,
+    pub decodable: bool,
+}
+
+pub fn ffprobe(url: &str, options: &HashMap<String, String>) -> Result<Probe> {
     ffmpeg_initialize()?;
 
     let c_url = CString::new(url)?;
     let mut format_context = ptr::null_mut();
 
+    // Opened with the same options as the decoder, so headers and cookies
+    // needed to reach the input apply here too
+    let c_options = options
+        .iter()
+        .map(|(key, value)| Ok((CString::new(key.as_str())?, CString::new(value.as_str())?)))
+        .collect::<Result<Vec<_>>>()?;
+
     unsafe {
-        if avformat_open_input(
+        let mut dictionary = ptr::null_mut();
+
+        for (key, value) in &c_options {
+            av_dict_set(&mut dictionary, key.as_ptr(), value.as_ptr(), 0);
+        }
+
+        let opened = avformat_open_input(
             &mut format_context,
             c_url.as_ptr(),
             ptr::null_mut(),
-            ptr::null_mut(),
-        ) != 0
-        {
+            &mut dictionary,
+        );
+
+        av_dict_free(&mut dictionary);
+
+        if opened != 0 {
             return Err(anyhow::anyhow!("Failed to open input"));
         }
 
         if avformat_find_stream_info(format_context, ptr::null_mut()) < 0 {
+            avformat_close_input(&mut format_context);
             return Err(anyhow::anyhow!("Failed to find stream info"));
         }
 
+        let stream = (*format_context).streams;
+        let video_stream_index = (0..(*format_context).nb_streams).find(|&i| {
+            let codec_parameters = (*(*stream.add(i as usize))).codecpar;
+            (*codec_parameters).codec_type == AVMediaType::AVMEDIA_TYPE_VIDEO
+        });
+
+        let Some(video_stream_index) = video_stream_index else {
+            avformat_close_input(&mut format_context);
+            return Err(anyhow::anyhow!("No video stream found"));
+        };
+
+        let codec_parameters = (*(*stream.add(video_stream_index as usize))).codecpar;
+        let frame_rate = (*codec_parameters).framerate;
+        let (width, height) = ((*codec_parameters).width, (*codec_parameters).height);
+        let codec_id = (*codec_parameters).codec_id;
+        let codec = CStr::from_ptr(avcodec_get_name(codec_id))
+            .to_string_lossy()
+            .into_owned();
+        let decodable = !avcodec_find_decoder(codec_id).is_null();
         let duration = (*format_context).duration;
+
         avformat_close_input(&mut format_context);
 
-        if duration != AV_NOPTS_VALUE {
-            Ok(DurationType::Fixed(
-                (duration as f64 / AV_TIME_BASE as f64) as u64,
-            ))
-        } else {
-            Ok(DurationType::Live)
+        if width <= 0 || height <= 0 {
+            return Err(anyhow::anyhow!("Invalid video size"));
         }
+
+        Ok(Probe {
+            fps: (frame_rate.den != 0).then(|| frame_rate.num as u64 / frame_rate.den as u64),
+            duration: if duration != AV_NOPTS_VALUE {
+                DurationType::Fixed((duration as f64 / AV_TIME_BASE as f64) as u64)
+            } else {
+                DurationType::Live
+            },
+            size: (width as u32, height as u32),
+            codec,
+            decodable,
+        })
     }
 }
@@ -1,3 +1,11 @@
 pub fn format_time(t: u64) -> String {
     format!("{:02}:{:02}:{:02}", t / 3600, (t % 3600) / 60, t % 60)
 }
+
+// SMPTE style HH:MM:SS:FF, the last field being the frame within the second
+pub fn format_timecode(t: f64, fps: u64) -> String {
+    let seconds = t.max(0.0);
+    let frame = (seconds.fract() * fps as f64) as u64;
+
+    format!("{}:{:02}", format_time(seconds as u64), frame)
+}
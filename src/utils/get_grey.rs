@@ -1,4 +1,5 @@
+// Rec. 709 luma, 0.2126 r + 0.7152 g + 0.0722 b, in fixed point out of 256
+// so it stays cheap on CPUs with slow floating point
 pub fn get_grey(r: u8, g: u8, b: u8) -> u8 {
-    // (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) as u8
-    0.2126f32.mul_add(r as f32, 0.7152f32.mul_add(g as f32, 0.0722 * b as f32)) as u8
+    ((54 * r as u32 + 183 * g as u32 + 19 * b as u32) >> 8) as u8
 }
@@ -0,0 +1,109 @@
+use image::{Rgb, RgbImage};
+
+use crate::utils::get_grey::get_grey;
+
+// Encoding errors are mostly a few levels off, scaled up so they show
+const HEATMAP_GAIN: u32 = 4;
+
+// Reported for identical pictures, where the PSNR is infinite
+const MAX_PSNR: f64 = 100.0;
+
+// Side of the square windows SSIM is averaged over
+const SSIM_WINDOW: u32 = 8;
+
+// Stabilizing constants from the SSIM paper, for 8-bit samples
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+// How far apart each pixel of two same sized pictures is, from black through
+// red and yellow to white
+pub fn difference_heatmap(a: &RgbImage, b: &RgbImage) -> RgbImage {
+    let mut heatmap = RgbImage::new(a.width(), a.height());
+
+    for ((pixel, Rgb(pixel_a)), Rgb(pixel_b)) in
+        heatmap.pixels_mut().zip(a.pixels()).zip(b.pixels())
+    {
+        let difference: u32 = pixel_a
+            .iter()
+            .zip(pixel_b)
+            .map(|(a, b)| a.abs_diff(*b) as u32)
+            .sum();
+        let heat = (difference * HEATMAP_GAIN / 3).min(255) * 3;
+
+        *pixel = Rgb([
+            heat.min(255) as u8,
+            heat.saturating_sub(255).min(255) as u8,
+            heat.saturating_sub(510) as u8,
+        ]);
+    }
+
+    heatmap
+}
+
+// Peak signal to noise ratio over all channels in dB, higher is closer
+pub fn psnr(a: &RgbImage, b: &RgbImage) -> f64 {
+    let samples = a.as_raw().iter().zip(b.as_raw());
+    let (sum, count) = samples.fold((0.0, 0), |(sum, count), (&a, &b)| {
+        let error = a as f64 - b as f64;
+
+        (sum + error * error, count + 1)
+    });
+
+    if sum == 0.0 || count == 0 {
+        return MAX_PSNR;
+    }
+
+    let mse = sum / count as f64;
+
+    (10.0 * (255.0 * 255.0 / mse).log10()).min(MAX_PSNR)
+}
+
+// Structural similarity of the luma, averaged over 8x8 windows, 1 when
+// identical
+pub fn ssim(a: &RgbImage, b: &RgbImage) -> f64 {
+    let luma = |image: &RgbImage, x: u32, y: u32| {
+        let Rgb([r, g, b]) = *image.get_pixel(x, y);
+
+        get_grey(r, g, b) as f64
+    };
+
+    let mut total = 0.0;
+    let mut windows = 0;
+
+    for top in (0..a.height()).step_by(SSIM_WINDOW as usize) {
+        for left in (0..a.width()).step_by(SSIM_WINDOW as usize) {
+            let xs = left..(left + SSIM_WINDOW).min(a.width());
+            let ys = top..(top + SSIM_WINDOW).min(a.height());
+            let samples: Vec<(f64, f64)> = ys
+                .flat_map(|y| xs.clone().map(move |x| (x, y)))
+                .map(|(x, y)| (luma(a, x, y), luma(b, x, y)))
+                .collect();
+
+            let n = samples.len() as f64;
+            let mean_a = samples.iter().map(|(a, _)| a).sum::<f64>() / n;
+            let mean_b = samples.iter().map(|(_, b)| b).sum::<f64>() / n;
+
+            let (mut var_a, mut var_b, mut covariance) = (0.0, 0.0, 0.0);
+
+            for (a, b) in &samples {
+                var_a += (a - mean_a) * (a - mean_a);
+                var_b += (b - mean_b) * (b - mean_b);
+                covariance += (a - mean_a) * (b - mean_b);
+            }
+
+            var_a /= n;
+            var_b /= n;
+            covariance /= n;
+
+            total += ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2));
+            windows += 1;
+        }
+    }
+
+    if windows == 0 {
+        return 1.0;
+    }
+
+    total / windows as f64
+}
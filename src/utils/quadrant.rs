@@ -0,0 +1,13 @@
+// Quadrant block for a 2x2 mask, bit 0 being the top left and bit 3 the bottom right
+pub fn quadrant_char(mask: u8) -> char {
+    [
+        ' ', '\u{2598}', '\u{259D}', '\u{2580}', '\u{2596}', '\u{258C}', '\u{259E}', '\u{259B}',
+        '\u{2597}', '\u{259A}', '\u{2590}', '\u{259C}', '\u{2584}', '\u{2599}', '\u{259F}',
+        '\u{2588}',
+    ][(mask & 0b1111) as usize]
+}
+
+// Half block for a 1x2 mask, bit 0 being the top and bit 1 the bottom
+pub fn half_block_char(mask: u8) -> char {
+    [' ', '\u{2580}', '\u{2584}', '\u{2588}'][(mask & 0b11) as usize]
+}
@@ -0,0 +1,123 @@
+use crossterm::style::Color;
+use std::sync::OnceLock;
+
+use crate::utils::args::ColorDepth;
+use crate::utils::rgb_distance::rgb_distance;
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (128, 0, 0)),
+    (Color::DarkGreen, (0, 128, 0)),
+    (Color::DarkYellow, (128, 128, 0)),
+    (Color::DarkBlue, (0, 0, 128)),
+    (Color::DarkMagenta, (128, 0, 128)),
+    (Color::DarkCyan, (0, 128, 128)),
+    (Color::Grey, (192, 192, 192)),
+    (Color::DarkGrey, (128, 128, 128)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (0, 0, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+// Quantized colors looked up by the top 5 bits of each channel. Frames reuse
+// the same colors over and over, so each table is filled once on first use and
+// shared from then on
+static ANSI_256_TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+static ANSI_16_TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+static ANSI_8_TABLE: OnceLock<Vec<u8>> = OnceLock::new();
+
+fn table_index((r, g, b): (u8, u8, u8)) -> usize {
+    ((r as usize >> 3) << 10) | ((g as usize >> 3) << 5) | (b as usize >> 3)
+}
+
+// Every 5-5-5 color quantized from the middle of the range it stands for
+fn build_table(quantize: fn((u8, u8, u8)) -> u8) -> Vec<u8> {
+    (0..1 << 15)
+        .map(|i: usize| {
+            let channel = |shift: usize| ((((i >> shift) & 31) << 3) | 4) as u8;
+
+            quantize((channel(10), channel(5), channel(0)))
+        })
+        .collect()
+}
+
+fn nearest_cube_level(v: u8) -> usize {
+    CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &level)| (level as i16 - v as i16).abs())
+        .map(|(i, _)| i)
+        .unwrap()
+}
+
+// Closest entry of the xterm 256 color palette, either from the 6x6x6 cube or the grey ramp
+fn ansi_256(rgb: (u8, u8, u8)) -> u8 {
+    let (r, g, b) = rgb;
+    let (ri, gi, bi) = (
+        nearest_cube_level(r),
+        nearest_cube_level(g),
+        nearest_cube_level(b),
+    );
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let grey_index = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey_level = 8 + grey_index * 10;
+
+    if rgb_distance(rgb, (grey_level, grey_level, grey_level)) < rgb_distance(rgb, cube) {
+        232 + grey_index
+    } else {
+        16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8
+    }
+}
+
+// Index into ANSI_16 of the closest of the standard colors
+fn ansi_16(rgb: (u8, u8, u8)) -> u8 {
+    nearest_ansi(&ANSI_16, rgb)
+}
+
+// Index into ANSI_16 of the closest of the 8 basic colors
+fn ansi_8(rgb: (u8, u8, u8)) -> u8 {
+    nearest_ansi(&ANSI_16[..8], rgb)
+}
+
+fn nearest_ansi(colors: &[(Color, (u8, u8, u8))], rgb: (u8, u8, u8)) -> u8 {
+    colors
+        .iter()
+        .enumerate()
+        .min_by(|(_, (_, a)), (_, (_, b))| rgb_distance(rgb, *a).total_cmp(&rgb_distance(rgb, *b)))
+        .map(|(i, _)| i as u8)
+        .unwrap()
+}
+
+// Reduce a color to what the terminal can display
+pub fn quantize_color(color: Color, depth: &ColorDepth) -> Color {
+    let Color::Rgb { r, g, b } = color else {
+        return color;
+    };
+
+    match depth {
+        ColorDepth::Truecolor => color,
+        ColorDepth::Ansi256 => {
+            let table = ANSI_256_TABLE.get_or_init(|| build_table(ansi_256));
+
+            Color::AnsiValue(table[table_index((r, g, b))])
+        }
+        ColorDepth::Ansi16 => {
+            let table = ANSI_16_TABLE.get_or_init(|| build_table(ansi_16));
+
+            ANSI_16[table[table_index((r, g, b))] as usize].0
+        }
+        ColorDepth::Ansi8 => {
+            let table = ANSI_8_TABLE.get_or_init(|| build_table(ansi_8));
+
+            ANSI_16[table[table_index((r, g, b))] as usize].0
+        }
+    }
+}
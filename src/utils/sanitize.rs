@@ -0,0 +1,32 @@
+// Longest a URL or path gets when printed, longer ones lose their middle
+const MAX_URL_CHARS: usize = 80;
+
+// Text from outside, like titles, URLs and error messages, made safe to print
+// into a raw mode terminal. Control characters, escape sequences included,
+// become spaces and bidi overrides that would reorder the rest of the line are
+// dropped
+pub fn sanitize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}'))
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect()
+}
+
+// A URL or path to print, cut down in the middle when it's very long so both
+// the host and the end of it stay readable
+pub fn display_url(url: &str) -> String {
+    let url = sanitize(url);
+    let chars: Vec<char> = url.chars().collect();
+
+    if chars.len() <= MAX_URL_CHARS {
+        return url;
+    }
+
+    let keep = (MAX_URL_CHARS - 1) / 2;
+
+    format!(
+        "{}…{}",
+        chars[..keep].iter().collect::<String>(),
+        chars[chars.len() - keep..].iter().collect::<String>()
+    )
+}
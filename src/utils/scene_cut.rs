@@ -0,0 +1,34 @@
+use image::{ImageBuffer, Rgb};
+
+use crate::utils::rgb_distance::rgb_distance;
+
+// Average color distance between two frames above which they're different shots
+const SCENE_CUT_DISTANCE: f32 = 60.0;
+
+// Compare a sparse grid of pixels, a cut changes the whole frame so that's enough
+pub fn is_scene_cut(
+    previous: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    current: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+) -> bool {
+    if previous.dimensions() != current.dimensions() {
+        return true;
+    }
+
+    let (width, height) = current.dimensions();
+    let step = (width.min(height) / 16).max(1) as usize;
+
+    let (total, count) = (0..height)
+        .step_by(step)
+        .flat_map(|y| (0..width).step_by(step).map(move |x| (x, y)))
+        .fold((0.0, 0), |(total, count), (x, y)| {
+            let a = previous.get_pixel(x, y);
+            let b = current.get_pixel(x, y);
+
+            (
+                total + rgb_distance((a[0], a[1], a[2]), (b[0], b[1], b[2])),
+                count + 1,
+            )
+        });
+
+    count > 0 && total / count as f32 > SCENE_CUT_DISTANCE
+}
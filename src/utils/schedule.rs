@@ -0,0 +1,22 @@
+use chrono::{Local, NaiveTime};
+use std::time::Duration;
+
+// Grace after the start time during which it still counts as now, so "--at
+// 21:00" typed at 21:00:30 starts right away instead of tomorrow
+const START_GRACE: chrono::Duration = chrono::Duration::minutes(1);
+
+// Time left until the next time the local clock reads `time`, today or tomorrow
+pub fn duration_until(time: NaiveTime) -> Duration {
+    let now = Local::now().naive_local();
+    let mut start = now.date().and_time(time);
+
+    if start <= now && now - start < START_GRACE {
+        return Duration::ZERO;
+    }
+
+    if start <= now {
+        start += chrono::Duration::days(1);
+    }
+
+    (start - now).to_std().unwrap_or_default()
+}
@@ -0,0 +1,14 @@
+// Sextant for a 2x3 mask, bit 0 being the top left and bit 5 the bottom right
+pub fn sextant_char(mask: u8) -> char {
+    match mask & 0b111111 {
+        0 => ' ',
+        // The full block and left/right halves already exist outside the sextant block
+        0b010101 => '\u{258C}',
+        0b101010 => '\u{2590}',
+        0b111111 => '\u{2588}',
+        mask => {
+            let skipped = (mask > 0b010101) as u32 + (mask > 0b101010) as u32;
+            char::from_u32(0x1FB00 + mask as u32 - 1 - skipped).unwrap()
+        }
+    }
+}
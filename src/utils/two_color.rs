@@ -0,0 +1,64 @@
+type Rgb = (u8, u8, u8);
+
+fn mean(sum: [u32; 3], count: u32) -> Rgb {
+    let count = count.max(1);
+
+    (
+        (sum[0] / count) as u8,
+        (sum[1] / count) as u8,
+        (sum[2] / count) as u8,
+    )
+}
+
+// Split a cell's pixels into a foreground and background color, trying every
+// mask (bit set = foreground) and keeping the one with the least squared error
+pub fn two_color_fit(pixels: &[Rgb]) -> (u8, Rgb, Rgb) {
+    let count = pixels.len();
+
+    let mut best = (0u8, (0, 0, 0), (0, 0, 0));
+    let mut best_error = f32::MAX;
+
+    for mask in 0..(1u16 << count) {
+        let mut fg_sum = [0u32; 3];
+        let mut bg_sum = [0u32; 3];
+        let mut fg_count = 0;
+
+        for (i, &(r, g, b)) in pixels.iter().enumerate() {
+            let sum = if mask & (1 << i) != 0 {
+                fg_count += 1;
+                &mut fg_sum
+            } else {
+                &mut bg_sum
+            };
+
+            sum[0] += r as u32;
+            sum[1] += g as u32;
+            sum[2] += b as u32;
+        }
+
+        let fg = mean(fg_sum, fg_count);
+        let bg = mean(bg_sum, count as u32 - fg_count);
+
+        let error: f32 = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, &(r, g, b))| {
+                let (cr, cg, cb) = if mask & (1 << i) != 0 { fg } else { bg };
+                let (dr, dg, db) = (
+                    r as f32 - cr as f32,
+                    g as f32 - cg as f32,
+                    b as f32 - cb as f32,
+                );
+
+                dr.mul_add(dr, dg.mul_add(dg, db * db))
+            })
+            .sum();
+
+        if error < best_error {
+            best_error = error;
+            best = (mask as u8, fg, bg);
+        }
+    }
+
+    best
+}
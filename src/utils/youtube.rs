@@ -1,9 +1,43 @@
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "youtube")]
 use youtube_dl::YoutubeDl;
 
-pub fn get_youtube_video_from_url(url: &str) -> anyhow::Result<(String, u64, String, bool)> {
+// A video format to fall back to
+#[derive(Clone, Serialize, Deserialize)]
+pub struct YoutubeFormat {
+    pub url: String,
+    pub fps: u64,
+    pub format: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct YoutubeVideo {
+    pub url: String,
+    pub fps: u64,
+    pub title: String,
+    pub live: bool,
+    pub artist: Option<String>,
+    pub thumbnail: Option<String>,
+    pub format: Option<String>,
+    // Other video formats, best first, for when the installed ffmpeg can't
+    // decode the chosen one
+    #[serde(default)]
+    pub fallbacks: Vec<YoutubeFormat>,
+}
+
+#[cfg(not(feature = "youtube"))]
+pub async fn get_youtube_video_from_url(_url: &str) -> anyhow::Result<YoutubeVideo> {
+    anyhow::bail!(
+        "YouTube links aren't supported by this build, it was built without the youtube feature"
+    )
+}
+
+#[cfg(feature = "youtube")]
+pub async fn get_youtube_video_from_url(url: &str) -> anyhow::Result<YoutubeVideo> {
     let output = YoutubeDl::new(url)
         .socket_timeout("15")
-        .run()?
+        .run_async()
+        .await?
         .into_single_video()
         .ok_or("No video found")
         .map_err(|e| anyhow::anyhow!(e))?;
@@ -14,21 +48,48 @@ pub fn get_youtube_video_from_url(url: &str) -> anyhow::Result<(String, u64, Str
         .map_err(|e| anyhow::anyhow!(e))?;
 
     let live = output.is_live.unwrap_or(false);
+    let artist = output.artist.or(output.uploader);
+    let thumbnail = output.thumbnail;
 
-    let output = output
+    // H.264 decodes everywhere, other codecs are only there to fall back on
+    let mut formats: Vec<((bool, u64), YoutubeFormat)> = output
         .formats
         .ok_or("No formats found")
         .map_err(|e| anyhow::anyhow!(e))?
         .into_iter()
-        .filter(|f| f.vcodec.clone().unwrap_or_default().contains("avc"))
-        .max_by_key(|f| (f.height.unwrap_or(0.0) + f.fps.unwrap_or(0.0)) as u64)
-        .ok_or("No suitable format found")
-        .map_err(|e| anyhow::anyhow!(e))?;
+        .filter(|f| f.vcodec.as_deref().is_some_and(|vcodec| vcodec != "none"))
+        .filter_map(|f| {
+            let avc = f.vcodec.as_deref().unwrap_or_default().contains("avc");
+            let rank = (f.height.unwrap_or(0.0) + f.fps.unwrap_or(0.0)) as u64;
+
+            Some((
+                (avc, rank),
+                YoutubeFormat {
+                    url: f.url?,
+                    fps: f.fps.unwrap_or(30.0) as u64,
+                    format: f.format_id,
+                },
+            ))
+        })
+        .collect();
 
-    let video_url = output
-        .url
-        .ok_or("No video URL found")
+    formats.sort_by_key(|(key, _)| std::cmp::Reverse(*key));
+
+    let mut formats = formats.into_iter().map(|(_, format)| format);
+
+    let best = formats
+        .next()
+        .ok_or("No suitable format found")
         .map_err(|e| anyhow::anyhow!(e))?;
 
-    Ok((video_url, output.fps.unwrap_or(30.0) as u64, title, live))
+    Ok(YoutubeVideo {
+        url: best.url,
+        fps: best.fps,
+        title,
+        live,
+        artist,
+        thumbnail,
+        format: best.format,
+        fallbacks: formats.collect(),
+    })
 }
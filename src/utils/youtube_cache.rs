@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use video_rs::Url;
+
+use crate::utils::youtube::YoutubeVideo;
+
+// How long resolved metadata is reused for, unless the stream URL expires sooner
+const CACHE_TTL_SECS: u64 = 10 * 60;
+
+// Stream URLs stop working at their expiry, leave time to actually play them
+const EXPIRY_MARGIN_SECS: u64 = 60;
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    expires: u64,
+    video: YoutubeVideo,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// The ID in watch?v=, youtu.be/, /shorts/ and /live/ links
+pub fn video_id(url: &str) -> Option<String> {
+    let url = url.parse::<Url>().ok()?;
+
+    if let Some((_, id)) = url.query_pairs().find(|(key, _)| key == "v") {
+        return Some(id.into_owned());
+    }
+
+    let mut segments = url.path_segments()?;
+
+    let id = match url.host_str()? {
+        "youtu.be" => segments.next()?,
+        _ => match segments.next()? {
+            "shorts" | "live" | "embed" => segments.next()?,
+            _ => return None,
+        },
+    };
+
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+fn cache_path(cache_dir: &Path, id: &str) -> PathBuf {
+    cache_dir.join("youtube").join(format!("{}.json", id))
+}
+
+pub fn load(cache_dir: &Path, id: &str) -> Option<YoutubeVideo> {
+    let json = fs::read_to_string(cache_path(cache_dir, id)).ok()?;
+    let entry: CacheEntry = serde_json::from_str(&json).ok()?;
+
+    (entry.expires > now()).then_some(entry.video)
+}
+
+// Failures are ignored, the cache only ever saves time
+pub fn store(cache_dir: &Path, id: &str, video: &YoutubeVideo) {
+    let path = cache_path(cache_dir, id);
+
+    // googlevideo URLs carry their expiry as a unix time in the query
+    let url_expires = video.url.parse::<Url>().ok().and_then(|url| {
+        url.query_pairs()
+            .find(|(key, _)| key == "expire")
+            .and_then(|(_, expire)| expire.parse::<u64>().ok())
+    });
+
+    let expires = match url_expires {
+        Some(expire) => (now() + CACHE_TTL_SECS).min(expire.saturating_sub(EXPIRY_MARGIN_SECS)),
+        None => now() + CACHE_TTL_SECS,
+    };
+
+    let entry = CacheEntry {
+        expires,
+        video: video.clone(),
+    };
+
+    let Ok(json) = serde_json::to_string(&entry) else {
+        return;
+    };
+
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    let _ = fs::write(path, json);
+}
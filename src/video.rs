@@ -1,46 +1,348 @@
 use anyhow::Context;
-use crossterm::cursor::MoveTo;
+use crossterm::cursor::{MoveTo, MoveToColumn};
 use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
 use crossterm::terminal::{Clear, ClearType};
 use crossterm::{queue, terminal};
-use image::{ImageBuffer, Rgb};
+use image::{imageops, ImageBuffer, Rgb};
 use ndarray::{ArrayBase, Dim, OwnedRepr};
+use serde::Serialize;
 use std::collections::HashMap;
-use std::io::{self};
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
 use tokio::time::Instant;
-use video_rs::{DecoderBuilder, Location, Options, Resize, Url};
-
-use crate::utils::args::{Args, HardwareAcceleration};
-use crate::utils::ffprobe::{
-    ffmpeg_initialize, ffprobe_get_duration, ffprobe_get_fps, DurationType,
+use unicode_width::UnicodeWidthStr;
+use video_rs::{Decoder, DecoderBuilder, Error, Location, Options, Resize, Url};
+
+use crate::alerts::Alert;
+use crate::capabilities::Capabilities;
+use crate::captions::Captions;
+use crate::effects::{apply_effects, apply_theme, theme_ramp, Effect, GhostTrail, EFFECT_CYCLE};
+use crate::env_defaults;
+use crate::external_audio::{default_audio_cmd, ExternalAudio};
+use crate::hooks::{HookState, Hooks};
+use crate::intro::IntroDetector;
+use crate::layout::{layout, truncate, Element};
+use crate::motion::MotionMap;
+use crate::now_playing::{write_now_playing, NowPlaying};
+use crate::paths::cache_dir;
+use crate::quality::{quality_level, QualityChange, QUALITY_LEVELS};
+use crate::recorder::Recorder;
+use crate::scopes::{render_scope, SCOPE_CYCLE, SCOPE_HEIGHT, SCOPE_WIDTH};
+use crate::smoothing::TemporalSmoothing;
+use crate::source::Source;
+use crate::stats::{ByteTally, Stats};
+use crate::subtitles::Subtitles;
+use crate::ttyrec::Ttyrec;
+use crate::url_refresh::refresh_url;
+use crate::utils::args::{
+    Args, ColorBlindFilter, ColorDepth, HardwareAcceleration, OnUnfocus, Theme,
 };
-use crate::utils::format_time::format_time;
+use crate::utils::backoff::backoff_delay;
+use crate::utils::braille::{braille_char, braille_fit};
+use crate::utils::color_blind::color_blind_filter;
+use crate::utils::emoji::nearest_emoji;
+use crate::utils::ffprobe::{ffmpeg_initialize, ffprobe, DurationType, Probe};
+use crate::utils::format_time::{format_time, format_timecode};
 use crate::utils::get_grey::get_grey;
+use crate::utils::quadrant::{half_block_char, quadrant_char};
+use crate::utils::quantize::quantize_color;
 use crate::utils::rgb_distance::rgb_distance;
-use crate::utils::step_size::step_size;
-use crate::utils::youtube::get_youtube_video_from_url;
+use crate::utils::sanitize::{display_url, sanitize};
+use crate::utils::scene_cut::is_scene_cut;
+use crate::utils::sextant::sextant_char;
+use crate::utils::two_color::two_color_fit;
+use crate::utils::youtube::{get_youtube_video_from_url, YoutubeVideo};
+use crate::utils::youtube_cache;
 use crate::{CharacterMode, ScaleMode};
 
 pub type Frame = ArrayBase<OwnedRepr<u8>, Dim<[usize; 3]>>;
 
+// A decoded frame with the stream's duration and the frame's timestamp in seconds
+pub type DecodedFrame = (Frame, DurationType, f64);
+
+// Asks the decoder to jump to `target` milliseconds. Once it's there it answers
+// with the timestamp playback resumes from and a fresh channel for the frames
+// after it, so nothing decoded before the seek can turn up again
+pub struct SeekRequest {
+    pub target: i64,
+    pub done: oneshot::Sender<(f64, UnboundedReceiver<DecodedFrame>)>,
+}
+
+// Have the decoder seek to `target` seconds and wait until it's there, for the
+// timestamp it resumes from and the frames after it
+pub async fn request_seek(
+    seek_tx: &UnboundedSender<SeekRequest>,
+    target: f32,
+) -> Option<(f64, UnboundedReceiver<DecodedFrame>)> {
+    let (done, acknowledged) = oneshot::channel();
+    let request = SeekRequest {
+        target: (target * 1000.0) as i64,
+        done,
+    };
+
+    seek_tx.send(request).ok()?;
+    acknowledged.await.ok()
+}
+
+// A decoded frame as an image to work on
+pub fn frame_image(frame: Frame) -> anyhow::Result<ImageBuffer<Rgb<u8>, Vec<u8>>> {
+    let (height, width) = (frame.shape()[0], frame.shape()[1]);
+
+    ImageBuffer::from_vec(
+        width as u32,
+        height as u32,
+        frame.into_raw_vec_and_offset().0,
+    )
+    .context("Decoded frame has an unexpected size")
+}
+
+// A seek the decoder has made but not yet decoded a frame after
+struct PendingSeek {
+    target: i64,
+    done: oneshot::Sender<(f64, UnboundedReceiver<DecodedFrame>)>,
+    frames: UnboundedReceiver<DecodedFrame>,
+}
+
+impl PendingSeek {
+    // Hand over the new frames, starting at `pts` seconds
+    fn acknowledge(self, pts: f64) {
+        let _ = self.done.send((pts, self.frames));
+    }
+}
+
+// Seek the source and switch to a new channel, leaving whatever was sent before
+// the seek in the old one. None when the input can't be sought or reopened,
+// playback has failed then
+fn begin_seek(
+    source: &mut Source,
+    frame_tx: &mut UnboundedSender<DecodedFrame>,
+    request: SeekRequest,
+    stats: &Stats,
+    reopen: impl Fn() -> anyhow::Result<Decoder>,
+) -> Option<PendingSeek> {
+    // Some inputs can't seek any more once they've been read to the end, a
+    // new decoder starts them over cleanly
+    if source.seek(request.target).is_err() {
+        match reopen() {
+            Ok(decoder) => {
+                source.reopen(decoder);
+
+                if request.target > 0 {
+                    let _ = source.seek(request.target);
+                }
+            }
+            // Acknowledging the seek would only have --loop ask for it again
+            Err(err) => {
+                stats.fail(format!("Seek failed: {:#}", err));
+                return None;
+            }
+        }
+    }
+
+    let (new_tx, frames) = unbounded_channel();
+    *frame_tx = new_tx;
+
+    Some(PendingSeek {
+        target: request.target,
+        done: request.done,
+        frames,
+    })
+}
+
+// Buffered seconds below which the connection is considered too slow
+const LOW_BUFFER_SECS: f32 = 1.0;
+
+// Attempts at recovering from a failed read before giving up on the stream
+const MAX_RETRIES: u32 = 5;
+
+// Cell heights in pixels from which each finer mode is still legible, below
+// the half-block one plain blocks are all that reads
+const HALF_BLOCK_MIN_CELL_HEIGHT: u16 = 10;
+const SEXTANT_MIN_CELL_HEIGHT: u16 = 16;
+const BRAILLE_MIN_CELL_HEIGHT: u16 = 24;
+
+// Framerate rendered at most in power saving mode
+const POWER_SAVE_FPS: u64 = 15;
+
+// Framerate rendered at most while unfocused with --on-unfocus throttle
+const UNFOCUSED_FPS: u64 = 2;
+
+// Smallest pixel clear distance in power saving mode
+const POWER_SAVE_CLEAR_DISTANCE: u16 = 8;
+
+// Size of the screen of a serial console, or of a terminal that can't be
+// asked, e.g. a --dry-run from a script without a tty
+const FALLBACK_SIZE: (u16, u16) = (80, 24);
+
+// Bytes a serial line gets to carry for each drawn frame, and the framerate
+// drawn at most however fast the line is
+const SERIAL_FRAME_BYTES: u32 = 1024;
+const SERIAL_MAX_FPS: u64 = 15;
+
+// How long the seek bar stays over the video after seeking
+const SEEK_BAR_DURATION: Duration = Duration::from_millis(1500);
+
+// Columns the title keeps in the header before other elements are dropped
+const TITLE_MIN_WIDTH: usize = 20;
+
+// How long a message stays over the video
+const OSD_DURATION: Duration = Duration::from_millis(1500);
+
+// An input resolved down to what gets decoded
+struct Resolved {
+    location: Location,
+    fps: u64,
+    title: String,
+    // yt-dlp's format id, for YouTube inputs
+    format: Option<String>,
+    probe: Probe,
+}
+
+// Printed by --dry-run
+#[derive(Serialize)]
+pub struct Plan {
+    pub input: String,
+    pub url: String,
+    pub format: Option<String>,
+    pub codec: String,
+    pub title: String,
+    pub live: bool,
+    pub fps: u64,
+    // None for live streams
+    pub duration: Option<u64>,
+    pub source_size: (u32, u32),
+    pub renderer: CharacterMode,
+    pub render_size: (u32, u32),
+}
+
+// Spinner shown while yt-dlp resolves a URL
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
+// Most subpixels any character mode packs into a single cell
+const MAX_SUBPIXELS: usize = 8;
+
 pub struct Video {
     pub url: String,
     pub title: String,
+    pub artist: Option<String>,
+    pub artwork: Option<String>,
     pub fps: u64,
     pub frame_times: Vec<Instant>,
     pub last_frame: Option<ImageBuffer<Rgb<u8>, Vec<u8>>>,
     pub character_mode: CharacterMode,
+    pub ramp: Option<String>,
     pub pixel_clear_distance: u16,
+    // Every nth subpixel of a cell is compared against the last frame, 1
+    // compares them all
+    diff_sampling: u32,
+    quality: usize,
     pub scale_mode: ScaleMode,
+    pub letterbox_color: Option<Color>,
+    // Character cell width:height, detected from the terminal when None
+    cell_aspect: Option<(u32, u32)>,
     pub remove_fps_cap: bool,
     pub fullscreen: bool,
     pub hw_accel: HardwareAcceleration,
     pub render_size: (u32, u32),
+    render_box: (u32, u32),
     pub no_color: bool,
+    pub color_depth: ColorDepth,
+    pub alt_screen: bool,
+    // Lookup of each channel value after gamma correction
+    gamma_table: Option<[u8; 256]>,
+    pub cb_filter: Option<ColorBlindFilter>,
+    pub effects: Vec<Effect>,
+    effect_cycle: usize,
+    // Earlier frame the ghost trail effect compares the next one against
+    pub trail_reference: Option<Frame>,
+    ghost_trail: GhostTrail,
+    scope_cycle: usize,
+    pub theme: Option<Theme>,
     pub live: bool,
+    pub network: bool,
+    pub http_headers: Vec<String>,
+    pub cookies: Option<String>,
+    pub url_refresh_cmd: Option<String>,
+    // Nowhere to cache with --no-cache
+    pub cache_dir: Option<PathBuf>,
+    pub stats: Arc<Stats>,
+    pub capabilities: Capabilities,
+    pub external_audio: Option<Arc<Mutex<ExternalAudio>>>,
+    // Audio was wanted but no player was found, said once playback starts
+    player_missing: bool,
+    pub captions: Option<Arc<Mutex<Captions>>>,
+    subtitles: Option<Subtitles>,
+    caption_shown: Option<String>,
+    pub input_fifo: Option<String>,
+    pub metrics: Option<String>,
+    pub kiosk: bool,
+    pub no_ui: bool,
+    pub timecode: bool,
+    pub power_save: bool,
+    pub on_unfocus: OnUnfocus,
+    // Most cells redrawn per frame, the ones with the most motion first
+    pub cell_budget: Option<usize>,
+    motion: MotionMap,
+    // Most bytes of escape sequences written for the cells of a frame
+    pub max_frame_bytes: Option<usize>,
+    smoothing: Option<TemporalSmoothing>,
+    // Baud rate of the serial console being rendered to
+    serial: Option<u32>,
+    byte_budget_start: usize,
+    pub record_source: Option<String>,
+    pub recorder: Option<Arc<Mutex<Recorder>>>,
+    pub ttyrec: Option<Arc<Mutex<Ttyrec>>>,
+    pub hooks: Arc<Hooks>,
+    pub now_playing_file: Option<String>,
+    pub alerts: Vec<Alert>,
+    // Where the last frame was drawn (column, row, columns, rows)
+    frame_area: (u32, u32, u32, u32),
+    // Part of the terminal to draw in (column, row, columns, rows), all of it
+    // unless several videos share the screen
+    pub viewport: Option<(u16, u16, u16, u16)>,
+    seek_bar_until: Option<Instant>,
+    // Where a seek still being held down on l or k will land
+    pub seek_preview: Option<f32>,
+    osd: Option<(String, Instant)>,
+    // Part of the video to play, from --start and --end in seconds
+    pub clip_start: f32,
+    clip_end: Option<f32>,
+    pub skip_intro: bool,
+    loop_playback: bool,
+    // Times left to start over, forever without a --loop-count
+    loops_left: Option<u32>,
+    pub sync_epoch: Option<f64>,
+    pub timeshift_max: Duration,
+    // Seconds a timeshifted live stream is behind the live edge
+    pub behind_live: f32,
+    intro_prompt_shown: bool,
+}
+
+// Framerate a serial line at this baud rate keeps up with, each byte taking a
+// start and a stop bit on top of its 8 data bits
+fn serial_fps(baud: u32) -> u64 {
+    ((baud / 10 / SERIAL_FRAME_BYTES) as u64).clamp(1, SERIAL_MAX_FPS)
+}
+
+fn gamma_table(gamma: f32) -> [u8; 256] {
+    std::array::from_fn(|value| ((value as f32 / 255.0).powf(1.0 / gamma) * 255.0).round() as u8)
+}
+
+// Huge cells have room for 2x4 braille dots, large ones for 2x3 sextants and
+// medium ones for two stacked half blocks. Tiny cells, or no reported size,
+// get plain blocks as the safe choice
+fn auto_character_mode(cell_size: Option<(u16, u16)>) -> CharacterMode {
+    match cell_size {
+        Some((_, height)) if height >= BRAILLE_MIN_CELL_HEIGHT => CharacterMode::Braille,
+        Some((_, height)) if height >= SEXTANT_MIN_CELL_HEIGHT => CharacterMode::Sextant,
+        Some((_, height)) if height >= HALF_BLOCK_MIN_CELL_HEIGHT => CharacterMode::HalfBlock,
+        _ => CharacterMode::Block,
+    }
 }
 
 enum VideoUrl {
@@ -66,138 +368,676 @@ impl std::str::FromStr for VideoUrl {
 }
 
 impl Video {
-    pub fn from_args(args: Args) -> Self {
-        Self {
+    pub fn from_args(args: Args) -> anyhow::Result<Self> {
+        // An explicit color depth means color was asked for, whatever NO_COLOR says
+        let no_color = args.no_color || (args.color_depth.is_none() && env_defaults::no_color());
+
+        // Nothing can be told about a serial console from here, and it should
+        // get nothing beyond VT100
+        let capabilities = match args.serial {
+            Some(_) => Capabilities::default(),
+            None => Capabilities::detect(),
+        };
+        let hooks = Arc::new(Hooks::from_args(&args)?);
+
+        let captions = args
+            .captions_cmd
+            .map(|command| Arc::new(Mutex::new(Captions::new(command, args.input.clone()))));
+        let subtitles = args.sub.as_deref().map(Subtitles::load).transpose()?;
+
+        let clip_start = args.start.unwrap_or(0.0);
+
+        if args.end.is_some_and(|end| end <= clip_start) {
+            anyhow::bail!("--end has to come after --start");
+        }
+
+        let loose = args.audio_from.is_some();
+        let audio_url = args.audio_from.unwrap_or_else(|| args.input.clone());
+
+        let external_audio = args
+            .audio_cmd
+            .or_else(|| default_audio_cmd(&audio_url))
+            .filter(|_| !args.no_audio)
+            .map(|command| Arc::new(Mutex::new(ExternalAudio::new(command, audio_url, loose))));
+        // Builds without the audio feature are silent on purpose
+        let player_missing = external_audio.is_none() && !args.no_audio && cfg!(feature = "audio");
+
+        let pixel_clear_distance = match args.pixel_clear_distance.unwrap_or(2) {
+            distance if args.power_save => distance.max(POWER_SAVE_CLEAR_DISTANCE),
+            distance => distance,
+        };
+
+        Ok(Self {
             title: "".to_string(),
+            artist: None,
+            artwork: None,
             fps: 0,
             url: args.input,
             frame_times: vec![],
             last_frame: None,
-            fullscreen: args.fullscreen,
-            remove_fps_cap: args.remove_fps_cap,
-            character_mode: args.mode.unwrap_or(CharacterMode::Block),
-            pixel_clear_distance: args.pixel_clear_distance.unwrap_or(2),
+            // Kiosk mode shows nothing but the video
+            fullscreen: args.fullscreen || args.kiosk || args.no_ui,
+            remove_fps_cap: args.remove_fps_cap && !args.power_save,
+            character_mode: if args.emoji {
+                CharacterMode::Emoji
+            } else {
+                match args.mode.unwrap_or_default() {
+                    CharacterMode::Auto if args.serial.is_some() => CharacterMode::Ascii,
+                    CharacterMode::Auto if args.low_end => CharacterMode::Block,
+                    CharacterMode::Auto => auto_character_mode(capabilities.cell_size),
+                    mode => mode,
+                }
+            },
+            ramp: args.ramp,
+            pixel_clear_distance,
+            diff_sampling: 1,
+            quality: quality_level(pixel_clear_distance),
             scale_mode: args.scale.unwrap_or(ScaleMode::Fit),
+            letterbox_color: args.letterbox_color,
+            cell_aspect: args.cell_aspect,
             hw_accel: args.hw_accel.unwrap_or(HardwareAcceleration::None),
             render_size: (0, 0),
-            no_color: args.no_color,
+            render_box: (0, 0),
+            no_color,
+            color_depth: args
+                .color_depth
+                .unwrap_or_else(|| match env_defaults::color_depth() {
+                    _ if args.serial.is_some() => ColorDepth::Ansi8,
+                    // Palette indices are a fraction of the bytes of RGB colors
+                    ColorDepth::Truecolor if args.low_end => ColorDepth::Ansi256,
+                    color_depth => color_depth,
+                }),
+            alt_screen: !args.no_alt_screen && args.serial.is_none(),
+            gamma_table: (args.gamma != 1.0).then(|| gamma_table(args.gamma)),
+            cb_filter: args.cb_filter,
+            effects: args.effects,
+            effect_cycle: 0,
+            trail_reference: None,
+            ghost_trail: GhostTrail::default(),
+            scope_cycle: 0,
+            theme: args.theme,
             live: false,
+            network: false,
+            http_headers: args.http_headers,
+            cookies: args.cookies,
+            url_refresh_cmd: args.url_refresh_cmd,
+            cache_dir: cache_dir(args.cache_dir.as_deref()).filter(|_| !args.no_cache),
+            stats: Arc::new(Stats::new(args.report, args.no_ui)),
+            capabilities,
+            external_audio,
+            player_missing,
+            captions,
+            subtitles,
+            caption_shown: None,
+            input_fifo: args.input_fifo,
+            metrics: args.metrics,
+            kiosk: args.kiosk,
+            no_ui: args.no_ui,
+            timecode: args.timecode,
+            power_save: args.power_save,
+            on_unfocus: args.on_unfocus.unwrap_or(if args.power_save {
+                OnUnfocus::Pause
+            } else {
+                OnUnfocus::Ignore
+            }),
+            cell_budget: args.cell_budget,
+            motion: MotionMap::default(),
+            max_frame_bytes: args.max_frame_bytes.or(args
+                .serial
+                .map(|baud| (baud / 10 / serial_fps(baud) as u32) as usize)),
+            smoothing: args
+                .smoothing
+                .filter(|&strength| strength > 0.0)
+                .map(TemporalSmoothing::new),
+            serial: args.serial,
+            byte_budget_start: 0,
+            record_source: args.record_source,
+            recorder: None,
+            ttyrec: args
+                .record_ttyrec
+                .as_deref()
+                .map(Ttyrec::create)
+                .transpose()?
+                .map(|ttyrec| Arc::new(Mutex::new(ttyrec))),
+            hooks,
+            now_playing_file: args.now_playing_file,
+            alerts: args.alerts,
+            frame_area: (0, 0, 0, 0),
+            viewport: None,
+            seek_bar_until: None,
+            seek_preview: None,
+            osd: None,
+            clip_start,
+            clip_end: args.end,
+            skip_intro: args.skip_intro,
+            loop_playback: args.loop_playback || args.loop_count.is_some() || args.kiosk,
+            loops_left: args.loop_count.map(|count| count - 1),
+            sync_epoch: args.sync_epoch,
+            timeshift_max: args.timeshift_max,
+            behind_live: 0.0,
+            intro_prompt_shown: false,
+        })
+    }
+
+    // Playback state handed to hook commands
+    pub fn hook_state<'a>(&'a self, event: &'a str, state: &'a str) -> HookState<'a> {
+        HookState {
+            event,
+            position: self.stats.position(),
+            title: &self.title,
+            state,
         }
     }
 
-    pub fn write_header(&self, stdout: &mut io::BufWriter<io::Stdout>) -> anyhow::Result<()> {
-        let (cols, rows) = terminal::size().unwrap();
+    pub fn write_now_playing(&self, state: &str, duration: DurationType) {
+        let Some(path) = &self.now_playing_file else {
+            return;
+        };
+
+        write_now_playing(
+            path,
+            &NowPlaying {
+                title: &self.title,
+                artist: self.artist.as_deref(),
+                position: self.stats.position(),
+                duration: match duration {
+                    DurationType::Fixed(duration) => Some(duration),
+                    DurationType::Live => None,
+                },
+                state,
+                artwork: self.artwork.as_deref(),
+            },
+        );
+    }
+
+    pub fn write_header(&self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let (cols, rows) = self.terminal_size();
         let (vid_cols, vid_rows) = self.render_size;
 
         if !self.fullscreen {
-            let playing_text = format!(" Playing: {} ", self.title);
-            let resolution_text = format!("{}x{}/{}x{}", vid_cols, vid_rows, cols, rows);
+            let mut elements = vec![
+                Element::truncate(4, format!("Playing: {}", self.title), TITLE_MIN_WIDTH),
+                Element::fill(3, 1, |width| " ".repeat(width)),
+            ];
+
+            if let Some(warning) = self.stats.current_warning() {
+                elements.push(Element::text(1, warning));
+            }
+
+            elements.push(Element::text(
+                2,
+                format!("{}x{}/{}x{}", vid_cols, vid_rows, cols, rows),
+            ));
 
             queue!(
                 stdout,
                 MoveTo(0, 0),
                 ResetColor,
-                Print(format!(
-                    "{}{}{}",
-                    playing_text,
-                    " ".repeat(
-                        (cols as usize)
-                            .saturating_sub(playing_text.len())
-                            .saturating_sub(resolution_text.len())
-                    ),
-                    resolution_text
-                ))
+                Clear(ClearType::CurrentLine),
+                Print(layout(cols as usize, elements))
             )?
         }
 
         Ok(())
     }
 
-    pub async fn fetch_video(
-        &mut self,
-        hw_accel: HardwareAcceleration,
-    ) -> anyhow::Result<(
-        UnboundedReceiver<(Frame, DurationType)>,
-        UnboundedSender<i64>,
-    )> {
+    // Work out what the input points at: the URL to decode, its framerate and size
+    async fn resolve(&mut self) -> anyhow::Result<Resolved> {
         ffmpeg_initialize()?;
 
         let video_type = self.url.parse::<VideoUrl>().unwrap();
 
-        let (video_url, fps, title) = match video_type {
+        let mut format = None;
+        let mut fallbacks = Vec::new();
+
+        let (mut location, mut fps, title) = match video_type {
             VideoUrl::YoutubeUrl(url) => {
-                let (video_url, fps, title, live) = get_youtube_video_from_url(&url)
-                    .with_context(|| format!("Failed to get video from {}", url))?;
+                let cache = self.cache_dir.clone().zip(youtube_cache::video_id(&url));
+
+                let youtube_video = match cache
+                    .as_ref()
+                    .and_then(|(dir, id)| youtube_cache::load(dir, id))
+                {
+                    Some(youtube_video) => youtube_video,
+                    None => {
+                        let youtube_video =
+                            resolve_youtube_video(&url).await.with_context(|| {
+                                format!("Failed to get video from {}", display_url(&url))
+                            })?;
+
+                        if let Some((dir, id)) = &cache {
+                            youtube_cache::store(dir, id, &youtube_video);
+                        }
+
+                        youtube_video
+                    }
+                };
 
-                self.live = live;
+                self.live = youtube_video.live;
+                self.network = true;
+                self.artist = youtube_video.artist;
+                self.artwork = youtube_video.thumbnail;
+                format = youtube_video.format;
+                fallbacks = youtube_video.fallbacks;
 
                 (
-                    Location::Network(video_url.parse::<Url>().unwrap()),
-                    fps,
-                    title,
+                    Location::Network(youtube_video.url.parse::<Url>().unwrap()),
+                    Some(youtube_video.fps),
+                    sanitize(&youtube_video.title),
                 )
             }
 
             VideoUrl::File(path) => {
-                let fps = ffprobe_get_fps(&path)
-                    .with_context(|| format!("Failed to get fps for {}", path))?;
+                let title = display_url(&path);
 
-                (Location::File(PathBuf::from(path.clone())), fps, path)
+                (Location::File(PathBuf::from(path)), None, title)
             }
 
             VideoUrl::DirectUrl(url) => {
-                let fps = ffprobe_get_fps(&url)
-                    .with_context(|| format!("Failed to get fps for {}", url))?;
+                self.network = true;
+
+                let title = display_url(&url);
 
-                (Location::Network(url.parse::<Url>().unwrap()), fps, url)
+                (Location::Network(url.parse::<Url>().unwrap()), None, title)
             }
         };
 
-        let (width, height) = terminal::size().unwrap();
+        // Opening a network input is the slow part of startup, so everything
+        // is read from a single probe
+        let mut probe = ffprobe(&location.to_string(), &self.input_options())
+            .with_context(|| format!("Failed to probe {}", display_url(&self.url)))?;
 
+        // Some ffmpeg builds lack decoders, e.g. AV1 without libdav1d, so try
+        // YouTube's other formats before giving up
+        let mut fallbacks = fallbacks.into_iter();
+
+        while !probe.decodable {
+            let Some(fallback) = fallbacks.next() else {
+                anyhow::bail!(
+                    "No decoder for {} video in the installed ffmpeg",
+                    probe.codec
+                );
+            };
+
+            let fallback_location = Location::Network(fallback.url.parse::<Url>()?);
+
+            // A format that fails to probe is no worse than the one before it
+            let Ok(fallback_probe) = ffprobe(&fallback_location.to_string(), &self.input_options())
+            else {
+                continue;
+            };
+
+            self.stats.warn(format!(
+                "Can't decode {}, using format {}",
+                probe.codec,
+                fallback.format.as_deref().unwrap_or("?")
+            ));
+
+            location = fallback_location;
+            fps = Some(fallback.fps);
+            format = fallback.format;
+            probe = fallback_probe;
+        }
+
+        let fps = fps
+            .or(probe.fps)
+            .with_context(|| format!("Failed to get fps for {}", display_url(&self.url)))?;
+
+        Ok(Resolved {
+            location,
+            fps,
+            title,
+            format,
+            probe,
+        })
+    }
+
+    // Frame size decoded to fill the terminal, and the box it's drawn in
+    // One decoded pixel per subpixel of the cell grid, so the scaler does all
+    // the downscaling and every sample is read 1:1
+    fn fit_to_terminal(&self, (video_width, video_height): (u32, u32)) -> (Resize, (u32, u32)) {
+        let (width, height) = self.terminal_size();
+        let (subpixel_width, subpixel_height) = self.cell_subpixels();
+
+        let columns = width as u32 / self.cell_width();
+        let mut rows = height as u32;
+
+        if !self.fullscreen {
+            rows = rows.saturating_sub(4);
+        }
+
+        let render_width = columns * subpixel_width;
+        let render_height = rows * subpixel_height;
+
+        // Decoded pixels aren't square on screen, so scale in screen units and
+        // convert back to subpixels along each axis
+        let (cell_width, cell_height) = self.cell_aspect();
+        let pixel_width = cell_width * self.cell_width() as f32 / subpixel_width as f32;
+        let pixel_height = cell_height / subpixel_height as f32;
+
+        let scale_x = render_width as f32 * pixel_width / video_width as f32;
+        let scale_y = render_height as f32 * pixel_height / video_height as f32;
+
+        let scaled = |scale: f32| {
+            (
+                (video_width as f32 * scale / pixel_width).round() as u32,
+                (video_height as f32 * scale / pixel_height).round() as u32,
+            )
+        };
+
+        let resize = match self.scale_mode {
+            ScaleMode::Fit => {
+                let (fit_width, fit_height) = scaled(scale_x.min(scale_y));
+
+                Resize::Exact(
+                    fit_width.clamp(1, render_width.max(1)),
+                    fit_height.clamp(1, render_height.max(1)),
+                )
+            }
+            ScaleMode::Stretch => Resize::Exact(render_width, render_height),
+            ScaleMode::Cover => {
+                // Scale by whichever side needs to grow more, the other side overflows
+                let (cover_width, cover_height) = scaled(scale_x.max(scale_y));
+
+                Resize::Exact(
+                    cover_width.max(render_width),
+                    cover_height.max(render_height),
+                )
+            }
+        };
+
+        (resize, (render_width, render_height))
+    }
+
+    // Frame size a still image is drawn at, for pictures that don't come from
+    // the decoder
+    pub fn still_size(&self, size: (u32, u32)) -> (u32, u32) {
+        match self.fit_to_terminal(size) {
+            (Resize::Exact(width, height), _) => (width, height),
+            (_, render_box) => render_box,
+        }
+    }
+
+    // A still image resized to be drawn by write_frame
+    pub fn fit_still(&self, still: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> anyhow::Result<Frame> {
+        let (width, height) = self.still_size(still.dimensions());
+        let resized = imageops::resize(
+            still,
+            width.max(1),
+            height.max(1),
+            imageops::FilterType::Triangle,
+        );
+
+        Ok(Frame::from_shape_vec(
+            (resized.height() as usize, resized.width() as usize, 3),
+            resized.into_raw(),
+        )?)
+    }
+
+    // ffmpeg options for opening the input, shared by the probe and the decoder
+    fn input_options(&self) -> HashMap<String, String> {
         let mut opts: HashMap<String, String> = HashMap::new();
 
         opts.insert("loglevel".to_string(), "quiet".to_string());
         opts.insert("nostats".to_string(), "1".to_string());
 
-        let options: Options = Options::from(opts);
-
-        let duration = ffprobe_get_duration(&video_url.to_string()).await?;
+        // Let ffmpeg reconnect dropped HTTP connections before we ever see an error
+        if self.network {
+            opts.insert("reconnect".to_string(), "1".to_string());
+            opts.insert("reconnect_streamed".to_string(), "1".to_string());
+            opts.insert("reconnect_on_network_error".to_string(), "1".to_string());
+            opts.insert("reconnect_delay_max".to_string(), "5".to_string());
+        }
 
-        let step_size = step_size();
+        // The HLS demuxer passes these on to its segment and AES-128 key requests
+        if !self.http_headers.is_empty() {
+            let headers: String = self
+                .http_headers
+                .iter()
+                .map(|header| format!("{}\r\n", header))
+                .collect();
 
-        let mut render_height = height as u32 * step_size;
-        let render_width = width as u32;
+            opts.insert("headers".to_string(), headers);
+        }
 
-        if !self.fullscreen {
-            render_height = render_height.saturating_sub(8);
+        if let Some(cookies) = &self.cookies {
+            opts.insert("cookies".to_string(), cookies.clone());
         }
 
-        let mut decoder = DecoderBuilder::new(video_url)
-            .with_resize(match self.scale_mode {
-                ScaleMode::Fit => Resize::Fit(render_width, render_height),
-                ScaleMode::Stretch => Resize::Exact(render_width, render_height),
-            })
-            .with_options(&options);
+        opts
+    }
 
-        if hw_accel != HardwareAcceleration::None {
-            decoder = decoder.with_hardware_acceleration(hw_accel.to_video_rs().unwrap());
-        }
+    // What playback would do with the input, without starting it
+    pub async fn plan(&mut self) -> anyhow::Result<Plan> {
+        let resolved = self.resolve().await?;
+        let (resize, render_box) = self.fit_to_terminal(resolved.probe.size);
+
+        Ok(Plan {
+            input: self.url.clone(),
+            url: resolved.location.to_string(),
+            format: resolved.format,
+            codec: resolved.probe.codec.clone(),
+            title: resolved.title,
+            live: self.live,
+            fps: resolved.fps,
+            duration: match resolved.probe.duration {
+                DurationType::Fixed(duration) => Some(duration),
+                DurationType::Live => None,
+            },
+            source_size: resolved.probe.size,
+            renderer: self.character_mode.clone(),
+            render_size: match self.scale_mode {
+                ScaleMode::Cover => render_box,
+                _ => resize
+                    .compute_for(resolved.probe.size)
+                    .unwrap_or(render_box),
+            },
+        })
+    }
 
-        let mut decoder = decoder.build().expect("failed to create decoder");
+    pub async fn fetch_video(
+        &mut self,
+        hw_accel: HardwareAcceleration,
+    ) -> anyhow::Result<(
+        UnboundedReceiver<DecodedFrame>,
+        UnboundedSender<SeekRequest>,
+    )> {
+        let Resolved {
+            location: video_url,
+            fps,
+            title,
+            probe,
+            ..
+        } = self.resolve().await?;
+
+        let duration = probe.duration;
+
+        let options: Options = Options::from(self.input_options());
+
+        let (resize, render_box) = self.fit_to_terminal(probe.size);
+
+        let open_stats = self.stats.clone();
+
+        // Reopening a refreshed URL has to decode exactly like the first open
+        let open = move |location: Location| {
+            let decoder = || {
+                DecoderBuilder::new(location.clone())
+                    .with_resize(resize)
+                    .with_options(&options)
+            };
+
+            // Static and reduced ffmpeg builds often lack the device, so
+            // decode in software rather than not at all
+            if let Some(device) = hw_accel.to_video_rs() {
+                match decoder().with_hardware_acceleration(device).build() {
+                    Ok(decoder) => return Ok(decoder),
+                    Err(err) => open_stats.warn(format!(
+                        "Hardware decoding unavailable ({}), decoding in software",
+                        err
+                    )),
+                }
+            }
+
+            decoder().build().context("Failed to create decoder")
+        };
+
+        // Where the input is now, refreshing the URL moves it
+        let mut location = video_url.clone();
+        let decoder = open(video_url)?;
+
+        self.render_box = render_box;
+        self.render_size = match self.scale_mode {
+            ScaleMode::Cover => self.render_box,
+            _ => decoder.size_out(),
+        };
 
-        self.render_size = decoder.size_out();
+        let (mut frame_tx, frame_rx) = unbounded_channel();
+        let (seek_tx, mut seek_rx) = unbounded_channel::<SeekRequest>();
 
-        let (frame_tx, frame_rx) = unbounded_channel();
-        let (seek_tx, mut seek_rx) = unbounded_channel();
+        let stats = self.stats.clone();
+        let kiosk = self.kiosk;
+        let clip_start = (self.clip_start as f64 * 1000.0) as i64;
+        let url_refresh_cmd = self.url_refresh_cmd.clone();
+        let mut intro_detector = self.skip_intro.then(IntroDetector::default);
+        let mut source = Source::new(decoder);
+
+        if let Some(path) = &self.record_source {
+            self.recorder = Some(source.record_to(path)?);
+        }
 
         tokio::spawn(async move {
-            while let Ok((_, frame)) = decoder.decode() {
-                if let Ok(seek) = seek_rx.try_recv() {
-                    decoder.seek(seek).unwrap();
+            let mut retries = 0;
+            let mut position = 0;
+            // Seek waiting on its first frame to be acknowledged
+            let mut seeking = None;
+            // Frames from the keyframe before --start up to it aren't part of the clip
+            let mut skip_until = 0;
+
+            if clip_start > 0 {
+                let _ = source.seek(clip_start);
+                position = clip_start;
+                skip_until = clip_start;
+            }
+
+            loop {
+                if let Ok(request) = seek_rx.try_recv() {
+                    skip_until = 0;
+                    position = request.target;
+                    seeking = begin_seek(&mut source, &mut frame_tx, request, &stats, || {
+                        open(location.clone())
+                    });
+
+                    if seeking.is_none() {
+                        break;
+                    }
                 }
 
-                frame_tx.send((frame, duration)).unwrap();
+                let decode_start = Instant::now();
+
+                let (time, frame) = match source.decode() {
+                    Ok(decoded) => decoded,
+                    // Kiosk displays loop the video forever
+                    Err(Error::DecodeExhausted) if kiosk => {
+                        let _ = source.seek(clip_start);
+                        position = clip_start;
+
+                        continue;
+                    }
+                    // Hold on at the end, the end screen can still replay or seek back
+                    Err(Error::DecodeExhausted) if matches!(duration, DurationType::Fixed(_)) => {
+                        stats.set_exhausted(true);
+
+                        // Sought past the end, there's nothing to resume from
+                        if let Some(seek) = seeking.take() {
+                            let target = seek.target as f64 / 1000.0;
+                            seek.acknowledge(target);
+                        }
+
+                        let Some(request) = seek_rx.recv().await else {
+                            break;
+                        };
+
+                        stats.set_exhausted(false);
+
+                        skip_until = 0;
+                        position = request.target;
+                        seeking = begin_seek(&mut source, &mut frame_tx, request, &stats, || {
+                            open(location.clone())
+                        });
+
+                        if seeking.is_none() {
+                            break;
+                        }
+
+                        continue;
+                    }
+                    Err(Error::DecodeExhausted) => break,
+                    // and never give up on a broken stream
+                    Err(_) if kiosk || retries < MAX_RETRIES => {
+                        retries = (retries + 1).min(MAX_RETRIES);
+                        stats.warn(format!(
+                            "Read failed, retrying ({}/{})",
+                            retries, MAX_RETRIES
+                        ));
+
+                        tokio::time::sleep(backoff_delay(retries)).await;
+
+                        // Signed URLs stop working once their token expires, so
+                        // swap in a fresh one before trying again
+                        if let Some(command) = &url_refresh_cmd {
+                            let refreshed = refresh_url(command).and_then(|refreshed| {
+                                location = refreshed.clone();
+                                open(refreshed)
+                            });
+
+                            match refreshed {
+                                Ok(decoder) => source.reopen(decoder),
+                                Err(err) => stats.warn(format!("URL refresh failed: {:#}", err)),
+                            }
+                        }
+
+                        // Reopen the stream where it broke off
+                        let _ = source.seek(position);
+
+                        continue;
+                    }
+                    Err(_) => {
+                        stats.fail("Stream lost".to_string());
+                        break;
+                    }
+                };
+
+                retries = 0;
+                position = (time.as_secs_f64() * 1000.0) as i64;
+
+                stats.record_decode(
+                    decode_start.elapsed(),
+                    time.as_secs_f64(),
+                    source.bytes_read(),
+                );
+
+                if position < skip_until {
+                    continue;
+                }
+
+                if let Some(detector) = &mut intro_detector {
+                    if let Some(intro_end) = detector.push(time.as_secs_f64(), &frame) {
+                        stats.set_intro_end(intro_end);
+                        intro_detector = None;
+                    }
+                }
+
+                if let Some(seek) = seeking.take() {
+                    seek.acknowledge(time.as_secs_f64());
+                }
+
+                // Nobody left to show the frames to
+                if frame_tx
+                    .send((frame, duration, time.as_secs_f64()))
+                    .is_err()
+                {
+                    break;
+                }
             }
         });
 
@@ -207,39 +1047,339 @@ impl Video {
         Ok((frame_rx, seek_tx))
     }
 
+    // Per-pixel color transforms applied before rendering
+    fn preprocess(&self, img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>) {
+        if self.gamma_table.is_none()
+            && self.cb_filter.is_none()
+            && self.effects.is_empty()
+            && self.theme.is_none()
+        {
+            return;
+        }
+
+        for pixel in img.pixels_mut() {
+            let mut rgb = (pixel[0], pixel[1], pixel[2]);
+
+            if let Some(table) = &self.gamma_table {
+                rgb = (
+                    table[rgb.0 as usize],
+                    table[rgb.1 as usize],
+                    table[rgb.2 as usize],
+                );
+            }
+
+            if let Some(filter) = &self.cb_filter {
+                rgb = color_blind_filter(filter, rgb);
+            }
+
+            rgb = apply_effects(&self.effects, rgb);
+
+            if let Some(theme) = &self.theme {
+                rgb = apply_theme(theme, rgb);
+            }
+
+            let (r, g, b) = rgb;
+            *pixel = Rgb([r, g, b]);
+        }
+    }
+
+    // How many frames back the ghost trail effect looks, while it's on
+    pub fn trail_frames(&self) -> Option<usize> {
+        self.effects.iter().find_map(|effect| match effect {
+            Effect::Trail(frames) => Some(*frames as usize),
+            _ => None,
+        })
+    }
+
+    // Switch to the next effect in the runtime cycle, replacing the current chain
+    pub fn cycle_effect(&mut self) {
+        self.effect_cycle = (self.effect_cycle + 1) % EFFECT_CYCLE.len();
+        self.effects = EFFECT_CYCLE[self.effect_cycle].into_iter().collect();
+
+        // Every pixel changes, so redraw the whole frame
+        self.last_frame = None;
+    }
+
+    // Switch to the next scope in the runtime cycle
+    pub fn cycle_scope(&mut self) {
+        self.scope_cycle = (self.scope_cycle + 1) % SCOPE_CYCLE.len();
+
+        // Bring back the part of the video the previous scope covered
+        self.last_frame = None;
+    }
+
+    // Step the quality slider, taking over the clear distance and sampling
+    pub fn change_quality(&mut self, change: QualityChange) {
+        self.quality = change.apply(self.quality);
+
+        let (distance, sampling) = QUALITY_LEVELS[self.quality];
+
+        self.pixel_clear_distance = distance;
+        self.diff_sampling = sampling;
+        self.last_frame = None;
+
+        self.show_osd(format!(
+            "Quality {}/{}: clear distance {}, comparing 1 in {} subpixels",
+            self.quality + 1,
+            QUALITY_LEVELS.len(),
+            distance,
+            sampling
+        ));
+    }
+
+    // Whether a frame gets drawn, frames left out still advance playback so
+    // drawing picks up in sync. Power saving and unfocused throttling only
+    // draw every nth frame to stay under their framerate
+    pub fn should_render(&self, frame_number: u64, focused: bool) -> bool {
+        let every = |fps: u64| frame_number.is_multiple_of(self.fps.div_ceil(fps).max(1));
+
+        let focus_allows = match (focused, &self.on_unfocus) {
+            (false, OnUnfocus::Pause) => false,
+            (false, OnUnfocus::Throttle) => every(UNFOCUSED_FPS),
+            _ => true,
+        };
+
+        focus_allows
+            && (!self.power_save || every(POWER_SAVE_FPS))
+            && self.serial.is_none_or(|baud| every(serial_fps(baud)))
+    }
+
+    // A serial console has no way of telling its size, so it gets the usual
+    // one, as does anything without a terminal
+    fn terminal_size(&self) -> (u16, u16) {
+        if let Some((_, _, columns, rows)) = self.viewport {
+            return (columns, rows);
+        }
+
+        match self.serial {
+            Some(_) => FALLBACK_SIZE,
+            None => terminal::size().unwrap_or(FALLBACK_SIZE),
+        }
+    }
+
+    // Whether to start over now that the video has ended, counting it
+    pub fn loop_again(&mut self) -> bool {
+        if !self.loop_playback {
+            return false;
+        }
+
+        match &mut self.loops_left {
+            None => true,
+            Some(0) => false,
+            Some(loops_left) => {
+                *loops_left -= 1;
+                true
+            }
+        }
+    }
+
+    // Where playback stops, the --end mark or else the end of the video
+    pub fn end_of_clip(&self, duration: DurationType) -> Option<f32> {
+        match duration {
+            DurationType::Fixed(duration) => Some(
+                self.clip_end
+                    .map_or(duration as f32, |end| end.min(duration as f32)),
+            ),
+            DurationType::Live => None,
+        }
+    }
+
+    // How long one time round takes when playback loops
+    pub fn loop_period(&self, duration: DurationType) -> Option<f32> {
+        self.end_of_clip(duration)
+            .filter(|_| self.loop_playback)
+            .map(|end| end - self.clip_start)
+    }
+
+    // A position and duration relative to the clip between --start and --end
+    fn clip_progress(&self, current_time: f32, duration: DurationType) -> (f32, DurationType) {
+        match self.end_of_clip(duration) {
+            Some(end) => (
+                (current_time - self.clip_start).max(0.0),
+                DurationType::Fixed((end - self.clip_start).max(0.0).round() as u64),
+            ),
+            None => (current_time, duration),
+        }
+    }
+
+    // Top left corner of the viewport
+    fn viewport_origin(&self) -> (u32, u32) {
+        self.viewport
+            .map_or((0, 0), |(column, row, ..)| (column as u32, row as u32))
+    }
+
+    // Terminal columns taken up by a single rendered pixel
+    fn cell_width(&self) -> u32 {
+        match self.character_mode {
+            CharacterMode::Emoji => 2,
+            _ => 1,
+        }
+    }
+
+    // Width and height of a character cell, from --cell-aspect, the pixel size
+    // the terminal reports, or the usual 1:2 of most fonts
+    fn cell_aspect(&self) -> (f32, f32) {
+        let (width, height) = self
+            .cell_aspect
+            .or(self
+                .capabilities
+                .cell_size
+                .map(|(width, height)| (width as u32, height as u32)))
+            .unwrap_or((1, 2));
+
+        (width as f32, height as f32)
+    }
+
+    // Subpixels sampled per terminal cell (columns, rows)
+    fn cell_subpixels(&self) -> (u32, u32) {
+        // A custom ramp replaces the mode's glyphs with one character per sample
+        if self.ramp.is_some() {
+            return (1, 1);
+        }
+
+        match self.character_mode {
+            CharacterMode::Block => (2, 2),
+            CharacterMode::HalfBlock => (1, 2),
+            CharacterMode::Braille => (2, 4),
+            CharacterMode::Sextant => (2, 3),
+            _ => (1, 1),
+        }
+    }
+
+    fn subpixel_char(&self, mask: u8) -> char {
+        match self.character_mode {
+            CharacterMode::Block => quadrant_char(mask),
+            CharacterMode::HalfBlock => half_block_char(mask),
+            CharacterMode::Braille => braille_char(mask),
+            CharacterMode::Sextant => sextant_char(mask),
+            _ => ' ',
+        }
+    }
+
+    // Fill the rows the video is drawn in, so the bars around it get the letterbox color
+    fn write_letterbox(&self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let Some(color) = self.letterbox_color else {
+            return Ok(());
+        };
+
+        let (terminal_width, terminal_height) = self.terminal_size();
+        let (origin_x, origin_y) = self.viewport_origin();
+        let y_offset = if !self.fullscreen { 2 } else { 0 };
+
+        queue!(
+            stdout,
+            SetBackgroundColor(quantize_color(color, &self.color_depth))
+        )?;
+
+        for row in y_offset..terminal_height.saturating_sub(y_offset) {
+            queue!(
+                stdout,
+                MoveTo(origin_x as u16, origin_y as u16 + row),
+                Print(" ".repeat(terminal_width as usize))
+            )?;
+        }
+
+        Ok(())
+    }
+
     pub fn write_frame(
         &mut self,
         frame: &Frame,
-        stdout: &mut io::BufWriter<io::Stdout>,
+        pts: f64,
+        stdout: &mut impl Write,
     ) -> anyhow::Result<()> {
         let frame_height = frame.shape()[0];
         let frame_width = frame.shape()[1];
 
-        let img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_vec(
+        let mut img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::from_vec(
             frame_width as u32,
             frame_height as u32,
             frame.as_slice().unwrap().to_vec(),
         )
         .unwrap();
 
-        let step_size: u32 = step_size();
+        if let Some(smoothing) = &mut self.smoothing {
+            smoothing.apply(&mut img);
+        }
 
-        let (terminal_width, _) = terminal::size().unwrap();
+        if let Some(reference) = self
+            .trail_reference
+            .as_ref()
+            .filter(|reference| reference.shape() == frame.shape())
+        {
+            self.ghost_trail
+                .apply(&mut img, reference.as_slice().unwrap_or_default());
+        }
 
-        let x_offset: u32 = if frame_width < terminal_width as usize {
-            (terminal_width as u32 - frame_width as u32) / 2
-        } else {
-            0
-        };
+        // Cover decodes larger than the render box, keep the centered part
+        if let ScaleMode::Cover = self.scale_mode {
+            let (box_width, box_height) = self.render_box;
+            let crop_width = box_width.min(img.width());
+            let crop_height = box_height.min(img.height());
+
+            img = imageops::crop_imm(
+                &img,
+                (img.width() - crop_width) / 2,
+                (img.height() - crop_height) / 2,
+                crop_width,
+                crop_height,
+            )
+            .to_image();
+        }
+
+        let frame_width = img.width() as usize;
+
+        if self.last_frame.is_none() {
+            self.write_letterbox(stdout)?;
+        }
 
-        let y_offset: u32 = if !self.fullscreen { 2 } else { 0 };
+        self.preprocess(&mut img);
+
+        // Redraw a new shot whole, so pixels that drifted by less than the clear
+        // distance over the previous shot don't linger into this one. The last
+        // frame is kept preprocessed, so this compares like with like
+        let scene_cut = self
+            .last_frame
+            .as_ref()
+            .is_some_and(|last_frame| is_scene_cut(last_frame, &img));
+
+        let cell_width = self.cell_width();
+        // Each decoded pixel is one subpixel of a cell
+        let (block_width, block_height) = self.cell_subpixels();
+        let subpixels = (block_width * block_height) as usize;
+
+        let (terminal_width, terminal_height) = self.terminal_size();
+
+        let (origin_x, origin_y) = self.viewport_origin();
+
+        let x_offset: u32 = origin_x
+            + (terminal_width as u32).saturating_sub(frame_width as u32 / block_width * cell_width)
+                / 2;
+
+        // Header and footer take two rows each outside of fullscreen
+        let margin: u32 = if !self.fullscreen { 2 } else { 0 };
+        let frame_rows = img.height().div_ceil(block_height);
+
+        let y_offset: u32 = origin_y
+            + margin
+            + (terminal_height as u32)
+                .saturating_sub(2 * margin)
+                .saturating_sub(frame_rows)
+                / 2;
+
+        self.frame_area = (
+            x_offset,
+            y_offset,
+            frame_width as u32 / block_width * cell_width,
+            frame_rows,
+        );
 
         let mut last_bg: Option<Color> = None;
         let mut last_fg: Option<Color> = None;
 
         let mut ramp: Vec<u32> = match self.character_mode {
             // █
-            CharacterMode::Block => [0x2588].to_vec(),
+            CharacterMode::Block | CharacterMode::Auto => [0x2588].to_vec(),
             // •
             CharacterMode::Dots => [0x2022].to_vec(),
             CharacterMode::Ascii => b"@#%*+=-:. ".to_vec().iter().map(|&x| x as u32).collect(),
@@ -261,8 +1401,42 @@ impl Video {
             CharacterMode::Numbers => b"1742350698".to_vec().iter().map(|&x| x as u32).collect(),
             // ░▒
             CharacterMode::Blocks => [0x2591, 0x2592].to_vec(),
+            // 🟥🟧🟨🟩🟦🟪🟫⬛⬜, picked by nearest color rather than brightness
+            CharacterMode::Emoji => [0x2B1C].to_vec(),
+            // Glyphs are picked per cell from the subpixel mask instead
+            CharacterMode::HalfBlock | CharacterMode::Braille | CharacterMode::Sextant => {
+                [0x2588].to_vec()
+            }
         };
 
+        let emoji = matches!(self.character_mode, CharacterMode::Emoji);
+
+        let text_mode = !emoji
+            && (self.ramp.is_some()
+                || !matches!(
+                    self.character_mode,
+                    CharacterMode::Block
+                        | CharacterMode::HalfBlock
+                        | CharacterMode::Braille
+                        | CharacterMode::Dots
+                        | CharacterMode::Sextant
+                ));
+
+        if let Some(theme_ramp) = self.theme.as_ref().and_then(theme_ramp) {
+            if text_mode {
+                ramp = theme_ramp;
+            }
+        }
+
+        if let Some(custom_ramp) = self.ramp.as_ref().filter(|_| text_mode) {
+            ramp = custom_ramp.chars().map(|x| x as u32).collect();
+        }
+
+        // Emoji bring their own colors
+        if emoji {
+            queue!(stdout, ResetColor)?;
+        }
+
         if self.no_color {
             if ramp[ramp.len() - 1] != ' ' as u32 {
                 ramp.append(&mut vec![' ' as u32]);
@@ -271,73 +1445,243 @@ impl Video {
             queue!(stdout, SetBackgroundColor(Color::Black))?;
         }
 
-        for y in (0..img.height()).step_by(step_size as usize) {
-            for x in 0..img.width() {
-                let pixel = img.get_pixel(x, y);
-                let r = pixel[0];
-                let g = pixel[1];
-                let b = pixel[2];
-
-                let needs_update = if let Some(last_frame) = &self.last_frame {
-                    let last_pixel = last_frame.get_pixel(x, y);
-                    let last_r = last_pixel[0];
-                    let last_g = last_pixel[1];
-                    let last_b = last_pixel[2];
-                    rgb_distance((r, g, b), (last_r, last_g, last_b))
-                        >= self.pixel_clear_distance as f32
-                } else {
-                    true
+        let (img_width, img_height) = img.dimensions();
+        let columns = img_width.div_ceil(block_width);
+
+        // Position of a subpixel in the cell at x, y
+        let subpixel_at = |x: u32, y: u32, i: usize| {
+            (
+                (x + i as u32 % block_width).min(img_width - 1),
+                (y + i as u32 / block_width).min(img_height - 1),
+            )
+        };
+
+        self.motion.resize((columns * frame_rows) as usize);
+
+        let mut changed: Vec<usize> = Vec::new();
+
+        for y in (0..img_height).step_by(block_height as usize) {
+            for x in (0..img_width).step_by(block_width as usize) {
+                let cell = ((y / block_height) * columns + x / block_width) as usize;
+
+                let Some(last_frame) = &self.last_frame else {
+                    changed.push(cell);
+                    continue;
                 };
 
-                if needs_update {
-                    let grey = get_grey(r, g, b);
+                let distance = (0..subpixels)
+                    .step_by(self.diff_sampling as usize)
+                    .map(|i| {
+                        let (sub_x, sub_y) = subpixel_at(x, y, i);
+                        let pixel = img.get_pixel(sub_x, sub_y);
+                        let last_pixel = last_frame.get_pixel(sub_x, sub_y);
 
-                    let ramp_len = ramp.len() as f32;
-                    let ramp_index = (grey as f32 / 255.0 * (ramp_len - 1.0)).round() as usize;
+                        rgb_distance(
+                            (pixel[0], pixel[1], pixel[2]),
+                            (last_pixel[0], last_pixel[1], last_pixel[2]),
+                        )
+                    })
+                    .fold(0.0, f32::max);
 
-                    let ascii = char::from_u32(ramp[ramp_index]).unwrap();
+                self.motion.record(cell, distance);
 
-                    if self.no_color {
-                        queue!(
-                            stdout,
-                            MoveTo((x + x_offset) as u16, ((y / step_size) + y_offset) as u16),
-                            Print(ascii)
-                        )?;
-                        continue;
-                    }
+                if scene_cut || distance >= self.pixel_clear_distance as f32 {
+                    changed.push(cell);
+                }
+            }
+        }
 
-                    let color = match self.character_mode {
-                        CharacterMode::Block | CharacterMode::Dots => Color::Rgb { r, g, b },
-                        CharacterMode::Ascii
-                        | CharacterMode::Numbers
-                        | CharacterMode::Blocks
-                        | CharacterMode::AsciiExtended
-                        | CharacterMode::AsciiWindows => Color::Rgb {
-                            r: 128,
-                            g: 128,
-                            b: 128,
-                        },
-                    };
+        // Over budget, the cells that moved least keep their old pixels so they
+        // still count as changed against the next frame
+        if let (Some(budget), Some(last_frame)) = (self.cell_budget, &self.last_frame) {
+            let deferred = self.motion.schedule(&mut changed, budget);
+
+            keep_cells(
+                &mut img,
+                last_frame,
+                &deferred,
+                columns,
+                (block_width, block_height),
+            );
+        }
 
-                    if last_bg != Some(Color::Rgb { r, g, b }) {
-                        queue!(stdout, SetBackgroundColor(Color::Rgb { r, g, b }))?;
-                    }
+        // Pick up where the byte budget ran out last frame, so no part of the
+        // screen is always the one left out
+        if self.max_frame_bytes.is_some() {
+            let start = changed.partition_point(|&cell| cell < self.byte_budget_start);
 
-                    queue!(
-                        stdout,
-                        MoveTo((x + x_offset) as u16, ((y / step_size) + y_offset) as u16),
-                    )?;
+            changed.rotate_left(start);
+        }
 
-                    if last_fg != Some(color) {
-                        queue!(stdout, SetForegroundColor(color))?;
-                    }
+        let cells_total = (columns * frame_rows) as u64;
+        let mut out_of_bytes_at = None;
+        let stdout = &mut ByteTally::new(stdout);
+
+        let mut samples = [(0u8, 0u8, 0u8); MAX_SUBPIXELS];
+
+        for (drawn, &cell) in changed.iter().enumerate() {
+            // A full redraw can't be cut short, there's nothing on screen to keep
+            let out_of_bytes = self
+                .max_frame_bytes
+                .is_some_and(|max_frame_bytes| stdout.bytes() >= max_frame_bytes);
+
+            if out_of_bytes && self.last_frame.is_some() {
+                out_of_bytes_at = Some(drawn);
+                break;
+            }
+
+            let x = (cell as u32 % columns) * block_width;
+            let y = (cell as u32 / columns) * block_height;
+
+            for (i, sample) in samples.iter_mut().take(subpixels).enumerate() {
+                let (sub_x, sub_y) = subpixel_at(x, y, i);
+                let pixel = img.get_pixel(sub_x, sub_y);
+
+                *sample = (pixel[0], pixel[1], pixel[2]);
+            }
+
+            let column = ((x / block_width) * cell_width + x_offset) as u16;
+            let row = ((y / block_height) + y_offset) as u16;
 
-                    queue!(stdout, Print(ascii))?;
+            if subpixels > 1 {
+                let cell = &samples[..subpixels];
 
-                    last_bg = Some(Color::Rgb { r, g, b });
-                    last_fg = Some(color);
+                if self.no_color {
+                    let mask = cell
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &(r, g, b))| get_grey(r, g, b) >= 128)
+                        .fold(0u8, |mask, (i, _)| mask | (1 << i));
+
+                    queue!(stdout, MoveTo(column, row), Print(self.subpixel_char(mask)))?;
+                    continue;
+                }
+
+                // Braille dots are too small to tell two colors apart
+                let (mask, (fg_r, fg_g, fg_b), (bg_r, bg_g, bg_b)) = match self.character_mode {
+                    CharacterMode::Braille => braille_fit(cell),
+                    _ => two_color_fit(cell),
+                };
+                let color = quantize_color(
+                    Color::Rgb {
+                        r: fg_r,
+                        g: fg_g,
+                        b: fg_b,
+                    },
+                    &self.color_depth,
+                );
+                let bg = quantize_color(
+                    Color::Rgb {
+                        r: bg_r,
+                        g: bg_g,
+                        b: bg_b,
+                    },
+                    &self.color_depth,
+                );
+
+                if last_bg != Some(bg) {
+                    queue!(stdout, SetBackgroundColor(bg))?;
                 }
+
+                queue!(stdout, MoveTo(column, row))?;
+
+                if last_fg != Some(color) {
+                    queue!(stdout, SetForegroundColor(color))?;
+                }
+
+                queue!(stdout, Print(self.subpixel_char(mask)))?;
+
+                last_bg = Some(bg);
+                last_fg = Some(color);
+                continue;
             }
+
+            let (r, g, b) = samples[0];
+
+            let grey = get_grey(r, g, b);
+
+            let ramp_len = ramp.len() as f32;
+            let ramp_index = (grey as f32 / 255.0 * (ramp_len - 1.0)).round() as usize;
+
+            let ascii = char::from_u32(ramp[ramp_index]).unwrap();
+
+            if emoji {
+                queue!(stdout, MoveTo(column, row), Print(nearest_emoji((r, g, b))))?;
+                continue;
+            }
+
+            if self.no_color {
+                queue!(stdout, MoveTo(column, row), Print(ascii))?;
+                continue;
+            }
+
+            let pixel_color = Color::Rgb { r, g, b };
+
+            let (color, bg) = if !text_mode {
+                (pixel_color, pixel_color)
+            } else if self.theme.is_some() {
+                // Themed text glows on a dark screen like a monochrome monitor
+                (pixel_color, Color::Black)
+            } else {
+                (
+                    Color::Rgb {
+                        r: 128,
+                        g: 128,
+                        b: 128,
+                    },
+                    pixel_color,
+                )
+            };
+
+            let color = quantize_color(color, &self.color_depth);
+            let bg = quantize_color(bg, &self.color_depth);
+
+            if last_bg != Some(bg) {
+                queue!(stdout, SetBackgroundColor(bg))?;
+            }
+
+            queue!(stdout, MoveTo(column, row),)?;
+
+            if last_fg != Some(color) {
+                queue!(stdout, SetForegroundColor(color))?;
+            }
+
+            queue!(stdout, Print(ascii))?;
+
+            last_bg = Some(bg);
+            last_fg = Some(color);
+        }
+
+        // Out of bytes, the rest keep their old pixels until a later frame
+        match (out_of_bytes_at, &self.last_frame) {
+            (Some(drawn), Some(last_frame)) => {
+                keep_cells(
+                    &mut img,
+                    last_frame,
+                    &changed[drawn..],
+                    columns,
+                    (block_width, block_height),
+                );
+                self.byte_budget_start = changed[drawn];
+            }
+            _ => self.byte_budget_start = 0,
+        }
+
+        self.stats.record_frame_output(
+            stdout.bytes() as u64,
+            out_of_bytes_at.unwrap_or(changed.len()) as u64,
+            cells_total,
+        );
+
+        // Drawn after the frame so it stays on top of whatever changed beneath it
+        if self.timecode {
+            queue!(
+                stdout,
+                MoveTo(x_offset as u16, y_offset as u16),
+                SetBackgroundColor(Color::Black),
+                SetForegroundColor(Color::White),
+                Print(format!(" {} ", format_timecode(pts, self.fps)))
+            )?;
         }
 
         self.last_frame = Some(img);
@@ -346,16 +1690,339 @@ impl Video {
         Ok(())
     }
 
+    // (Re)start the external audio at a position. A player that won't start
+    // is reported and left out, playback carries on silently
+    pub fn start_audio(&mut self, position: f32) {
+        let Some(external_audio) = &self.external_audio else {
+            if std::mem::take(&mut self.player_missing) {
+                self.stats
+                    .warn("No audio, install mpv or ffplay to hear it".to_string());
+            }
+
+            return;
+        };
+
+        let started = external_audio.lock().unwrap().start(position);
+
+        if let Err(err) = started {
+            self.stats.warn(format!("No audio: {:#}", err));
+            self.external_audio = None;
+        }
+    }
+
+    // (Re)start the live captions at a position, like the audio a transcriber
+    // that won't start is reported and left out
+    pub fn start_captions(&mut self, position: f32) {
+        let Some(captions) = &self.captions else {
+            return;
+        };
+
+        let started = captions.lock().unwrap().start(position);
+
+        if let Err(err) = started {
+            self.stats.warn(format!("No captions: {:#}", err));
+            self.captions = None;
+        }
+    }
+
+    // A seek is being held down, show where it's going and play a moment of
+    // the audio from there
+    pub fn preview_seek(&mut self, target: f32) {
+        self.seek_preview = Some(target);
+
+        if let Some(external_audio) = &self.external_audio {
+            let _ = external_audio.lock().unwrap().scrub(target);
+        }
+    }
+
+    // Called once a seek has gone through, so there's nothing left to preview
+    pub fn show_seek_bar(&mut self) {
+        self.seek_bar_until = Some(Instant::now() + SEEK_BAR_DURATION);
+        self.seek_preview = None;
+    }
+
+    // Seek position bar over the bottom row of the video, shown for a moment after seeking
+    pub fn write_seek_bar(
+        &mut self,
+        stdout: &mut impl Write,
+        current_time: f32,
+        duration: DurationType,
+    ) -> anyhow::Result<()> {
+        let Some(until) = self.seek_bar_until else {
+            return Ok(());
+        };
+
+        if Instant::now() >= until {
+            self.seek_bar_until = None;
+
+            // Bring back the part of the video the bar covered
+            self.last_frame = None;
+
+            return Ok(());
+        }
+
+        let (current_time, duration) = self.clip_progress(current_time, duration);
+
+        let DurationType::Fixed(duration) = duration else {
+            return Ok(());
+        };
+
+        let (column, row, columns, rows) = self.frame_area;
+
+        let time_text = format!(
+            " {} / {}",
+            format_time(current_time as u64),
+            format_time(duration)
+        );
+
+        let bar_width = (columns as usize).saturating_sub(time_text.len() + 2);
+        let progress = (current_time / duration.max(1) as f32).clamp(0.0, 1.0);
+        let watched = (progress * bar_width as f32) as usize;
+
+        queue!(
+            stdout,
+            MoveTo(column as u16, (row + rows.saturating_sub(1)) as u16),
+            SetBackgroundColor(Color::Black),
+            SetForegroundColor(Color::White),
+            Print(format!(
+                "[{}{}]{}",
+                "=".repeat(watched),
+                " ".repeat(bar_width - watched),
+                time_text
+            ))
+        )?;
+
+        Ok(())
+    }
+
+    // Scope of the current frame over the bottom left of the video, above the seek bar
+    pub fn write_scope(&self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let (Some(scope), Some(frame)) = (SCOPE_CYCLE[self.scope_cycle], &self.last_frame) else {
+            return Ok(());
+        };
+
+        let (column, row, columns, rows) = self.frame_area;
+
+        if (columns as usize) < SCOPE_WIDTH || (rows as usize) < SCOPE_HEIGHT + 1 {
+            return Ok(());
+        }
+
+        let top = row + rows - 1 - SCOPE_HEIGHT as u32;
+
+        queue!(stdout, SetBackgroundColor(Color::Black))?;
+
+        for (i, line) in render_scope(scope, frame).iter().enumerate() {
+            queue!(stdout, MoveTo(column as u16, (top + i as u32) as u16))?;
+
+            for &(character, channel) in line {
+                let color = match channel {
+                    Some(0) => Color::Red,
+                    Some(1) => Color::Green,
+                    Some(_) => Color::Blue,
+                    None => Color::White,
+                };
+
+                queue!(stdout, SetForegroundColor(color), Print(character))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn show_osd(&mut self, message: String) {
+        self.osd = Some((sanitize(&message), Instant::now() + OSD_DURATION));
+    }
+
+    // Message over the top right of the video, shown for a moment
+    pub fn write_osd(&mut self, stdout: &mut impl Write) -> anyhow::Result<()> {
+        let Some((message, until)) = &self.osd else {
+            return Ok(());
+        };
+
+        if Instant::now() >= *until {
+            self.osd = None;
+
+            // Bring back the part of the video the message covered
+            self.last_frame = None;
+
+            return Ok(());
+        }
+
+        let message = format!(" {} ", message);
+        let (column, row, columns, _) = self.frame_area;
+
+        queue!(
+            stdout,
+            MoveTo(
+                (column + columns).saturating_sub(message.chars().count() as u32) as u16,
+                row as u16
+            ),
+            SetBackgroundColor(Color::Black),
+            SetForegroundColor(Color::White),
+            Print(message)
+        )?;
+
+        Ok(())
+    }
+
+    // Summary centered over the video once it has played to the end
+    pub fn write_end_screen(
+        &mut self,
+        stdout: &mut impl Write,
+        watched: Duration,
+        frames: u64,
+        dropped: u64,
+    ) -> anyhow::Result<()> {
+        let (column, row, columns, rows) = self.frame_area;
+        let width = columns as usize;
+
+        let lines = [
+            layout(width, vec![Element::truncate(0, self.title.clone(), 0)]),
+            layout(
+                width,
+                vec![
+                    Element::text(3, format!("Watched {}", format_time(watched.as_secs()))),
+                    Element::text(
+                        2,
+                        format!(
+                            "{:.1} fps average",
+                            frames as f32 / watched.as_secs_f32().max(1.0)
+                        ),
+                    ),
+                    Element::text(1, format!("{} dropped", dropped)),
+                ],
+            ),
+            layout(
+                width,
+                vec![
+                    Element::text(1, "n for next".to_string()),
+                    Element::text(2, "r to replay".to_string()),
+                    Element::text(3, "q to quit".to_string()),
+                ],
+            ),
+        ];
+
+        let box_width = lines.iter().map(|line| line.width()).max().unwrap_or(0);
+        let top = row + rows.saturating_sub(lines.len() as u32) / 2;
+
+        for (i, line) in lines.iter().enumerate() {
+            queue!(
+                stdout,
+                MoveTo(
+                    (column + columns.saturating_sub(box_width as u32) / 2) as u16,
+                    (top + i as u32) as u16
+                ),
+                SetBackgroundColor(Color::Black),
+                SetForegroundColor(Color::White),
+                Print(format!("{}{}", line, " ".repeat(box_width - line.width())))
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Subtitle for the frame at `pts`, or else the latest caption, centered
+    // near the bottom of the video
+    pub fn write_captions(&mut self, stdout: &mut impl Write, pts: f64) -> anyhow::Result<()> {
+        let caption = self
+            .subtitles
+            .as_ref()
+            .and_then(|subtitles| subtitles.cue_at(pts))
+            .or_else(|| {
+                self.captions
+                    .as_ref()
+                    .and_then(|captions| captions.lock().unwrap().current())
+            });
+
+        if caption != self.caption_shown {
+            // Bring back the part of the video the previous caption covered
+            if self.caption_shown.is_some() {
+                self.last_frame = None;
+            }
+
+            self.caption_shown = caption.clone();
+        }
+
+        let Some(caption) = caption else {
+            return Ok(());
+        };
+
+        let (column, row, columns, rows) = self.frame_area;
+        let lines: Vec<&str> = caption.lines().collect();
+
+        // Multi-line subtitles grow upwards from where a single line sits.
+        // Cues and transcripts come from outside, so they're sanitized
+        for (i, line) in lines.iter().enumerate() {
+            let line = truncate(&format!(" {} ", sanitize(line)), columns as usize);
+            let above = (lines.len() - i) as u32;
+
+            queue!(
+                stdout,
+                MoveTo(
+                    (column + columns.saturating_sub(line.width() as u32) / 2) as u16,
+                    (row + rows.saturating_sub(1 + above)) as u16
+                ),
+                SetBackgroundColor(Color::Black),
+                SetForegroundColor(Color::White),
+                Print(line)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    // Offer to skip ahead while the detected intro is playing
+    pub fn write_intro_prompt(
+        &mut self,
+        stdout: &mut impl Write,
+        current_time: f32,
+    ) -> anyhow::Result<()> {
+        let show = self.skip_intro
+            && self
+                .stats
+                .intro_end()
+                .is_some_and(|intro_end| current_time < intro_end - 1.0);
+
+        if !show {
+            if self.intro_prompt_shown {
+                self.intro_prompt_shown = false;
+
+                // Bring back the part of the video the prompt covered
+                self.last_frame = None;
+            }
+
+            return Ok(());
+        }
+
+        let prompt = " Skip intro [s] ";
+        let (column, row, columns, rows) = self.frame_area;
+
+        queue!(
+            stdout,
+            MoveTo(
+                (column + columns).saturating_sub(prompt.len() as u32 + 1) as u16,
+                (row + rows).saturating_sub(2) as u16
+            ),
+            SetBackgroundColor(Color::Black),
+            SetForegroundColor(Color::White),
+            Print(prompt)
+        )?;
+
+        self.intro_prompt_shown = true;
+
+        Ok(())
+    }
+
     pub fn write_footer(
         &self,
-        stdout: &mut io::BufWriter<io::Stdout>,
+        stdout: &mut impl Write,
         render_fps: f64,
         current_time: f32,
         duration: DurationType,
         elapsed: Duration,
         time_since_start: Duration,
     ) -> anyhow::Result<()> {
-        let (width, height) = terminal::size().unwrap();
+        let (width, height) = self.terminal_size();
 
         queue!(
             stdout,
@@ -365,110 +2032,177 @@ impl Video {
         )
         .unwrap();
 
-        let mut frame_time_text = format!(
+        let frame_time_text = format!(
             "{:>width$}ms",
             format!("{:.2}", elapsed.as_secs_f64() * 1000.0),
             // over 1000ms is unlikely, and if so then they have other problems
             width = 6
         );
 
-        // let fps_text = format!("FPS: {:.0}/{}", render_fps, self.fps);
-        let mut fps_text = format!(
+        let fps_text = format!(
             "{:>width$}",
             format!("FPS: {:.0}/{}", render_fps, self.fps),
             width = 11
         );
 
-        let (current_time_str, duration_str, progress_bar) = match duration {
-            DurationType::Fixed(duration) => {
-                let duration = duration as f32;
-                let progress = current_time / duration;
-
-                let current_time_str = format_time(current_time as u64);
-                let duration_str = format_time(duration as u64);
-
-                // let space = width as usize
-                //     - current_time_str.len()
-                //     - duration_str.len()
-                //     - fps_text.len()
-                //     - frame_time.len()
-                //     - 10;
-                let mut space = (width as usize).saturating_sub(
-                    current_time_str.len()
-                        + duration_str.len()
-                        + fps_text.len()
-                        + frame_time_text.len()
-                        + 10,
-                );
-
-                if space < 1 {
-                    fps_text = "".to_string();
-                    frame_time_text = "".to_string();
-                    space = (width as usize).saturating_sub(
-                        current_time_str.len()
-                            + duration_str.len()
-                            + fps_text.len()
-                            + frame_time_text.len()
-                            + 8,
-                    );
-                }
-
-                let watched_space = (progress * (space as f32)) as usize;
-                let remaining_space = (space - watched_space) as usize;
+        let (current_time, duration) = self.clip_progress(current_time, duration);
+        let current_time_str = format_time(current_time as u64);
 
-                let progress_bar = format!(
-                    "[{}{}]",
-                    "=".repeat(watched_space),
-                    " ".repeat(remaining_space)
-                );
-
-                (current_time_str, duration_str, progress_bar)
+        let mut elements = match duration {
+            DurationType::Fixed(duration) => {
+                let progress = (current_time / duration.max(1) as f32).clamp(0.0, 1.0);
+
+                vec![
+                    Element::text(5, format!("{}/{}", current_time_str, format_time(duration))),
+                    Element::fill(4, 2, move |width| {
+                        let space = width.saturating_sub(2);
+                        let watched_space = (progress * space as f32) as usize;
+
+                        format!(
+                            "[{}{}]",
+                            "=".repeat(watched_space),
+                            " ".repeat(space - watched_space)
+                        )
+                    }),
+                ]
             }
             DurationType::Live => {
-                let frame_time = format!(
-                    // pad left with spaces
-                    "{:>width$}ms",
-                    format!("{:.2}", elapsed.as_secs_f64() * 1000.0),
-                    // over 1000ms is unlikely, and if so then they have other problems
-                    width = 6
-                );
-
-                let current_time_str = format_time(current_time as u64);
-
-                let duration_str = "Live".to_string();
-
                 let bar = "<=====>";
 
-                let space = width as usize
-                    - current_time_str.len()
-                    - duration_str.len()
-                    - fps_text.len()
-                    - frame_time.len()
-                    - bar.len()
-                    - 9;
+                vec![
+                    Element::text(
+                        5,
+                        if self.behind_live >= 1.0 {
+                            format!(
+                                "{}/Live -{}",
+                                current_time_str,
+                                format_time(self.behind_live as u64)
+                            )
+                        } else {
+                            format!("{}/Live", current_time_str)
+                        },
+                    ),
+                    Element::fill(4, bar.len() + 2, move |width| {
+                        let space = width.saturating_sub(bar.len() + 2);
+                        let watched_space =
+                            (time_since_start.as_secs_f32() * 10.0) as usize % (space + 1);
+
+                        format!(
+                            "[{}{}{}]",
+                            " ".repeat(watched_space),
+                            bar,
+                            " ".repeat(space - watched_space)
+                        )
+                    }),
+                ]
+            }
+        };
 
-                let watched_space = (time_since_start.as_secs_f32() * 10.0 % space as f32) as usize;
+        if self.network {
+            let network = self.stats.network_health(current_time);
+
+            elements.push(Element::text(
+                2,
+                format!(
+                    "{:.1}Mb/s buf: {:.1}s{}",
+                    network.bitrate / 1_000_000.0,
+                    network.buffer.max(0.0),
+                    if network.buffer < LOW_BUFFER_SECS {
+                        " (slow connection)"
+                    } else {
+                        ""
+                    }
+                ),
+            ));
+        }
 
-                let remaining_space = space.saturating_sub(watched_space);
+        if let Some(cpu_usage) = self.stats.cpu_usage().filter(|_| self.power_save) {
+            elements.push(Element::text(0, format!("CPU: {:.0}%", cpu_usage)));
+        }
 
-                let progress_bar = format!(
-                    "[{}{}{}]",
-                    " ".repeat(watched_space),
-                    bar,
-                    " ".repeat(remaining_space)
-                );
+        if let Some(max_frame_bytes) = self.max_frame_bytes {
+            elements.push(Element::text(
+                1,
+                format!(
+                    "{:.1}/{:.1}KiB",
+                    self.stats.last_frame_bytes() as f64 / 1024.0,
+                    max_frame_bytes as f64 / 1024.0
+                ),
+            ));
+        }
 
-                (current_time_str, duration_str, progress_bar)
-            }
-        };
+        if let Some(target) = self.seek_preview {
+            elements.insert(
+                1,
+                Element::text(5, format!("Seek to {}", format_time(target as u64))),
+            );
+        }
+
+        elements.push(Element::text(3, fps_text));
+        elements.push(Element::text(1, frame_time_text));
 
+        // The last column is left alone so the bottom line never wraps
         queue!(
             stdout,
-            Print(format!(
-                " {}/{} {} {} {} ",
-                current_time_str, duration_str, progress_bar, fps_text, frame_time_text
-            ))
+            Print(layout((width as usize).saturating_sub(1), elements))
         )
         .map_err(|e| anyhow::anyhow!(e))
     }
 }
+
+// Resolve a YouTube URL with yt-dlp, spinning on the current line meanwhile
+// so the terminal isn't left blank for the seconds it takes. The spinner goes
+// to stderr to keep stdout clean for --dry-run
+async fn resolve_youtube_video(url: &str) -> anyhow::Result<YoutubeVideo> {
+    let mut stderr = std::io::stderr();
+    let mut resolving = std::pin::pin!(get_youtube_video_from_url(url));
+    let mut spinner = tokio::time::interval(SPINNER_INTERVAL);
+    let mut frame = 0;
+
+    loop {
+        tokio::select! {
+            youtube_video = &mut resolving => {
+                queue!(stderr, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+                stderr.flush()?;
+
+                return youtube_video;
+            }
+            _ = spinner.tick() => {
+                queue!(
+                    stderr,
+                    MoveToColumn(0),
+                    Print(format!(
+                        "{} Resolving {} (Ctrl-C to cancel)",
+                        SPINNER_FRAMES[frame % SPINNER_FRAMES.len()],
+                        display_url(url)
+                    ))
+                )?;
+                stderr.flush()?;
+
+                frame += 1;
+            }
+        }
+    }
+}
+
+// Put a cell's pixels from the previous frame back, leaving it as it is on screen
+fn keep_cells(
+    img: &mut ImageBuffer<Rgb<u8>, Vec<u8>>,
+    last_frame: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    cells: &[usize],
+    columns: u32,
+    (block_width, block_height): (u32, u32),
+) {
+    let (img_width, img_height) = img.dimensions();
+
+    for &cell in cells {
+        let x = (cell as u32 % columns) * block_width;
+        let y = (cell as u32 / columns) * block_height;
+
+        for pixel_y in y..(y + block_height).min(img_height) {
+            for pixel_x in x..(x + block_width).min(img_width) {
+                img.put_pixel(pixel_x, pixel_y, *last_frame.get_pixel(pixel_x, pixel_y));
+            }
+        }
+    }
+}